@@ -3,7 +3,7 @@ use density_mesh_core::prelude::*;
 use density_mesh_image::prelude::*;
 use image::{DynamicImage, GenericImage, GenericImageView};
 use obj_exporter::{Geometry, ObjSet, Object, Primitive, Shape, TVertex, Vertex};
-use std::fs::write;
+use std::fs::{read_to_string, write};
 
 fn main() {
     run_app(make_app().get_matches());
@@ -67,6 +67,18 @@ fn make_app<'a, 'b>() -> App<'a, 'b> {
                         .default_value("1")
                         .takes_value(true)
                         .required(false),
+                )
+                .arg(
+                    Arg::with_name("steepness-kernel")
+                        .long("steepness-kernel")
+                        .value_name("NAME")
+                        .help(
+                            "Steepness kernel: difference-average, sobel, scharr, laplacian, \
+                            gradient, gradient:<RADIUS>",
+                        )
+                        .default_value("difference-average")
+                        .takes_value(true)
+                        .required(false),
                 ),
         )
         .subcommand(
@@ -150,6 +162,18 @@ fn make_app<'a, 'b>() -> App<'a, 'b> {
                         .takes_value(true)
                         .required(false),
                 )
+                .arg(
+                    Arg::with_name("steepness-kernel")
+                        .long("steepness-kernel")
+                        .value_name("NAME")
+                        .help(
+                            "Steepness kernel: difference-average, sobel, scharr, laplacian, \
+                            gradient, gradient:<RADIUS>",
+                        )
+                        .default_value("difference-average")
+                        .takes_value(true)
+                        .required(false),
+                )
                 .arg(
                     Arg::with_name("points-separation")
                         .long("points-separation")
@@ -168,6 +192,18 @@ fn make_app<'a, 'b>() -> App<'a, 'b> {
                         .takes_value(true)
                         .required(false),
                 )
+                .arg(
+                    Arg::with_name("triangle-visibility")
+                        .long("triangle-visibility")
+                        .value_name("NAME")
+                        .help(
+                            "Triangle visibility criterion: coverage, coverage:<RATIO>, \
+                            any-pixel, all-pixels, centroid, mean-density, mean-density:<VALUE>",
+                        )
+                        .default_value("coverage")
+                        .takes_value(true)
+                        .required(false),
+                )
                 .arg(
                     Arg::with_name("steepness-threshold")
                         .long("steepness-threshold")
@@ -186,6 +222,15 @@ fn make_app<'a, 'b>() -> App<'a, 'b> {
                         .takes_value(true)
                         .required(false),
                 )
+                .arg(
+                    Arg::with_name("max-step-rejections")
+                        .long("max-step-rejections")
+                        .value_name("INTEGER")
+                        .help("Maximum rejected point candidates checked in single step")
+                        .default_value("32")
+                        .takes_value(true)
+                        .required(false),
+                )
                 .arg(
                     Arg::with_name("extrude-size")
                         .long("extrude-size")
@@ -194,6 +239,41 @@ fn make_app<'a, 'b>() -> App<'a, 'b> {
                         .takes_value(true)
                         .required(false),
                 )
+                .arg(
+                    Arg::with_name("extrude-join")
+                        .long("extrude-join")
+                        .value_name("NAME")
+                        .help("Shape of extruded outline corners")
+                        .default_value("miter")
+                        .possible_values(&["miter", "bevel", "round"])
+                        .takes_value(true)
+                        .required(false),
+                )
+                .arg(
+                    Arg::with_name("miter-limit")
+                        .long("miter-limit")
+                        .value_name("NUMBER")
+                        .help("Maximal ratio of miter length to extrude size")
+                        .default_value("4")
+                        .takes_value(true)
+                        .required(false),
+                )
+                .arg(
+                    Arg::with_name("extrude-rings")
+                        .long("extrude-rings")
+                        .value_name("INTEGER")
+                        .help("Number of extruded outline rings")
+                        .default_value("1")
+                        .takes_value(true)
+                        .required(false),
+                )
+                .arg(
+                    Arg::with_name("extrude-inward")
+                        .long("extrude-inward")
+                        .help("Extrude outline inside of mesh")
+                        .takes_value(false)
+                        .required(false),
+                )
                 .arg(
                     Arg::with_name("update-region-margin")
                         .long("update-region-margin")
@@ -203,6 +283,131 @@ fn make_app<'a, 'b>() -> App<'a, 'b> {
                         .takes_value(true)
                         .required(false),
                 )
+                .arg(
+                    Arg::with_name("points-placement")
+                        .long("points-placement")
+                        .value_name("NAME")
+                        .help("Points placement strategy")
+                        .default_value("steepness")
+                        .possible_values(&[
+                            "steepness",
+                            "poisson-disc",
+                            "error-driven",
+                            "tight-polygon",
+                        ])
+                        .takes_value(true)
+                        .required(false),
+                )
+                .arg(
+                    Arg::with_name("error-tolerance")
+                        .long("error-tolerance")
+                        .value_name("NUMBER")
+                        .help("Maximal approximation error of error driven points placement")
+                        .default_value("0.01")
+                        .takes_value(true)
+                        .required(false),
+                )
+                .arg(
+                    Arg::with_name("max-points")
+                        .long("max-points")
+                        .value_name("INTEGER")
+                        .help("Maximal number of points of error driven points placement")
+                        .takes_value(true)
+                        .required(false),
+                )
+                .arg(
+                    Arg::with_name("simplification")
+                        .long("simplification")
+                        .value_name("NAME")
+                        .help("Outline simplification method of tight polygon points placement")
+                        .default_value("douglas-peucker")
+                        .possible_values(&["douglas-peucker", "visvalingam"])
+                        .takes_value(true)
+                        .required(false),
+                )
+                .arg(
+                    Arg::with_name("simplification-tolerance")
+                        .long("simplification-tolerance")
+                        .value_name("NUMBER")
+                        .help("Outline simplification tolerance of tight polygon points placement")
+                        .default_value("1")
+                        .takes_value(true)
+                        .required(false),
+                )
+                .arg(
+                    Arg::with_name("max-vertices")
+                        .long("max-vertices")
+                        .value_name("INTEGER")
+                        .help("Maximal number of vertices of each simplified outline")
+                        .takes_value(true)
+                        .required(false),
+                )
+                .arg(
+                    Arg::with_name("inflate")
+                        .long("inflate")
+                        .help("Inflate simplified outline so it covers all visible pixels")
+                        .takes_value(false)
+                        .required(false),
+                )
+                .arg(
+                    Arg::with_name("seed")
+                        .long("seed")
+                        .value_name("INTEGER")
+                        .help("Seed of random numbers used by points placement")
+                        .default_value("0")
+                        .takes_value(true)
+                        .required(false),
+                )
+                .arg(
+                    Arg::with_name("constrain-contour")
+                        .long("constrain-contour")
+                        .help("Constrain triangulation to the outline of visible area")
+                        .takes_value(false)
+                        .required(false),
+                )
+                .arg(
+                    Arg::with_name("min-angle")
+                        .long("min-angle")
+                        .value_name("NUMBER")
+                        .help("Minimal angle of refined triangles in degrees")
+                        .takes_value(true)
+                        .required(false),
+                )
+                .arg(
+                    Arg::with_name("max-triangle-area")
+                        .long("max-triangle-area")
+                        .value_name("NUMBER")
+                        .help("Maximal area of refined triangles")
+                        .takes_value(true)
+                        .required(false),
+                )
+                .arg(
+                    Arg::with_name("max-steiner-points")
+                        .long("max-steiner-points")
+                        .value_name("INTEGER")
+                        .help("Maximal number of points inserted by refinement")
+                        .takes_value(true)
+                        .required(false),
+                )
+                .arg(
+                    Arg::with_name("attributes")
+                        .long("attributes")
+                        .value_name("NAMES")
+                        .help("Comma separated per point attributes put in mesh")
+                        .possible_values(&["values", "steepness", "uvs", "outline", "all"])
+                        .use_delimiter(true)
+                        .takes_value(true)
+                        .required(false),
+                )
+                .arg(
+                    Arg::with_name("height-scale")
+                        .long("height-scale")
+                        .value_name("NUMBER")
+                        .help("Height of OBJ mesh points with density value of 1")
+                        .default_value("32")
+                        .takes_value(true)
+                        .required(false),
+                )
                 .arg(
                     Arg::with_name("keep-invisible-triangles")
                         .long("keep-invisible-triangles")
@@ -220,6 +425,62 @@ fn make_app<'a, 'b>() -> App<'a, 'b> {
                         .required(true),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("stats")
+                .about("Measure how well density mesh approximates image")
+                .arg(
+                    Arg::with_name("input")
+                        .short("i")
+                        .long("input")
+                        .value_name("PATH")
+                        .help("Input image file")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("mesh")
+                        .short("m")
+                        .long("mesh")
+                        .value_name("PATH")
+                        .help("Input JSON or YAML mesh file")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Print JSON report")
+                        .takes_value(false)
+                        .required(false),
+                )
+                .arg(
+                    Arg::with_name("density-source")
+                        .long("density-source")
+                        .value_name("NAME")
+                        .help("Density source: luma, luma-alpha, red, green, blue, alpha")
+                        .default_value("luma-alpha")
+                        .takes_value(true)
+                        .required(false),
+                )
+                .arg(
+                    Arg::with_name("scale")
+                        .long("scale")
+                        .value_name("INTEGER")
+                        .help("Image scale")
+                        .default_value("1")
+                        .takes_value(true)
+                        .required(false),
+                )
+                .arg(
+                    Arg::with_name("visibility-threshold")
+                        .long("visibility-threshold")
+                        .value_name("NUMBER")
+                        .help("Visibility threshold")
+                        .default_value("0.01")
+                        .takes_value(true)
+                        .required(false),
+                ),
+        )
 }
 
 fn run_app(matches: ArgMatches) {
@@ -243,9 +504,15 @@ fn run_app(matches: ArgMatches) {
                 .unwrap()
                 .parse::<usize>()
                 .expect("Could not parse integer");
+            let steepness_kernel = matches
+                .value_of("steepness-kernel")
+                .unwrap()
+                .parse::<SteepnessKernel>()
+                .expect("Could not parse steepness kernel");
             let settings = GenerateDensityImageSettings {
                 density_source,
                 scale,
+                steepness_kernel,
             };
             if verbose {
                 println!("{:#?}", settings);
@@ -278,9 +545,15 @@ fn run_app(matches: ArgMatches) {
                 .unwrap()
                 .parse::<usize>()
                 .expect("Could not parse integer");
+            let steepness_kernel = matches
+                .value_of("steepness-kernel")
+                .unwrap()
+                .parse::<SteepnessKernel>()
+                .expect("Could not parse steepness kernel");
             let settings = GenerateDensityImageSettings {
                 density_source,
                 scale,
+                steepness_kernel,
             };
             if verbose {
                 println!("{:#?}", settings);
@@ -310,30 +583,144 @@ fn run_app(matches: ArgMatches) {
                 .unwrap()
                 .parse::<usize>()
                 .expect("Could not parse integer");
+            let max_step_rejections = matches
+                .value_of("max-step-rejections")
+                .unwrap()
+                .parse::<usize>()
+                .expect("Could not parse integer");
             let extrude_size = matches
                 .value_of("extrude-size")
                 .map(|v| v.parse::<Scalar>().expect("Could not parse number"));
+            let extrude = ExtrudeSettings {
+                join: match matches.value_of("extrude-join").unwrap() {
+                    "miter" => ExtrudeJoin::Miter,
+                    "bevel" => ExtrudeJoin::Bevel,
+                    "round" => ExtrudeJoin::Round,
+                    id => panic!("Unsupported value: {}", id),
+                },
+                miter_limit: matches
+                    .value_of("miter-limit")
+                    .unwrap()
+                    .parse::<Scalar>()
+                    .expect("Could not parse number"),
+                rings: matches
+                    .value_of("extrude-rings")
+                    .unwrap()
+                    .parse::<usize>()
+                    .expect("Could not parse integer"),
+                direction: if matches.is_present("extrude-inward") {
+                    ExtrudeDirection::Inward
+                } else {
+                    ExtrudeDirection::Outward
+                },
+            };
+            let update_region_margin = matches
+                .value_of("update-region-margin")
+                .unwrap()
+                .parse::<Scalar>()
+                .expect("Could not parse number");
+            let points_placement = match matches.value_of("points-placement").unwrap() {
+                "steepness" => PointsPlacement::Steepness,
+                "poisson-disc" => PointsPlacement::PoissonDisc,
+                "error-driven" => PointsPlacement::ErrorDriven {
+                    error_tolerance: matches
+                        .value_of("error-tolerance")
+                        .unwrap()
+                        .parse::<Scalar>()
+                        .expect("Could not parse number"),
+                    max_points: matches
+                        .value_of("max-points")
+                        .map(|v| v.parse::<usize>().expect("Could not parse integer")),
+                },
+                "tight-polygon" => PointsPlacement::TightPolygon(ContourSimplification {
+                    method: match matches.value_of("simplification").unwrap() {
+                        "douglas-peucker" => SimplificationMethod::DouglasPeucker,
+                        "visvalingam" => SimplificationMethod::Visvalingam,
+                        id => panic!("Unsupported value: {}", id),
+                    },
+                    tolerance: matches
+                        .value_of("simplification-tolerance")
+                        .unwrap()
+                        .parse::<Scalar>()
+                        .expect("Could not parse number"),
+                    max_vertices: matches
+                        .value_of("max-vertices")
+                        .map(|v| v.parse::<usize>().expect("Could not parse integer")),
+                    inflate: matches.is_present("inflate"),
+                }),
+                id => panic!("Unsupported value: {}", id),
+            };
+            let seed = matches
+                .value_of("seed")
+                .unwrap()
+                .parse::<u64>()
+                .expect("Could not parse integer");
+            let constrain_contour = matches.is_present("constrain-contour");
+            let min_angle = matches
+                .value_of("min-angle")
+                .map(|v| v.parse::<Scalar>().expect("Could not parse number"));
+            let max_triangle_area = matches
+                .value_of("max-triangle-area")
+                .map(|v| v.parse::<Scalar>().expect("Could not parse number"));
+            let max_steiner_points = matches
+                .value_of("max-steiner-points")
+                .map(|v| v.parse::<usize>().expect("Could not parse integer"));
+            let mut attributes = MeshAttributes::default();
+            for name in matches.values_of("attributes").into_iter().flatten() {
+                match name {
+                    "values" => attributes.values = true,
+                    "steepness" => attributes.steepness = true,
+                    "uvs" => attributes.uvs = true,
+                    "outline" => attributes.outline = true,
+                    "all" => attributes = MeshAttributes::all(),
+                    id => panic!("Unsupported value: {}", id),
+                }
+            }
             let keep_invisible_triangles = matches.is_present("keep-invisible-triangles");
+            let triangle_visibility = matches
+                .value_of("triangle-visibility")
+                .unwrap()
+                .parse::<TriangleVisibility>()
+                .expect("Could not parse triangle visibility");
             let settings = GenerateDensityMeshSettings {
                 points_separation,
                 visibility_threshold,
                 steepness_threshold,
                 max_iterations,
+                max_step_rejections,
                 extrude_size,
+                extrude,
+                attributes,
                 keep_invisible_triangles,
+                triangle_visibility,
+                update_region_margin,
+                points_placement,
+                seed,
+                constrain_contour,
+                min_angle,
+                max_triangle_area,
+                max_steiner_points,
             };
             if verbose {
                 println!("{:#?}", settings);
             }
+            let height_scale = matches
+                .value_of("height-scale")
+                .unwrap()
+                .parse::<Scalar>()
+                .expect("Could not parse number");
             let mut generator = DensityMeshGenerator::new(vec![], map, settings);
             if verbose {
                 generator
-                    .process_wait_tracked(|current, limit, percentage| {
+                    .process_wait_tracked(|report| {
                         println!(
-                            "Progress: {}% ({} / {})",
-                            (percentage * 100.0).max(0.0).min(100.0),
-                            current,
-                            limit
+                            "Progress: {}% - {:?}: {}% ({} / {}), points: {}",
+                            (report.overall_progress * 100.0).clamp(0.0, 100.0),
+                            report.stage,
+                            (report.stage_progress * 100.0).clamp(0.0, 100.0),
+                            report.current,
+                            report.limit,
+                            report.points,
                         );
                     })
                     .expect("Cannot produce density mesh");
@@ -342,6 +729,14 @@ fn run_app(matches: ArgMatches) {
                     .process_wait()
                     .expect("Cannot produce density mesh");
             }
+            let heightfield = if obj {
+                generator.mesh().map(|mesh| {
+                    HeightfieldMesh::new(mesh, generator.map(), height_scale)
+                        .expect("Cannot produce heightfield mesh")
+                })
+            } else {
+                None
+            };
             let mesh = generator.into_mesh().expect("Cannot produce density mesh");
             if json {
                 let contents = serde_json::to_string(&mesh).expect("Could not serialize JSON mesh");
@@ -354,15 +749,16 @@ fn run_app(matches: ArgMatches) {
                 let contents = serde_yaml::to_string(&mesh).expect("Could not serialize YAML mesh");
                 write(output, contents).expect("Could not save mesh file");
             } else if obj {
+                let heightfield = heightfield.expect("Cannot produce density mesh");
                 let object = Object {
                     name: "mesh".to_owned(),
-                    vertices: mesh
+                    vertices: heightfield
                         .points
                         .iter()
-                        .map(|p| Vertex {
-                            x: p.x as _,
-                            y: p.y as _,
-                            z: 0.0,
+                        .map(|[x, y, z]| Vertex {
+                            x: *x as _,
+                            y: *y as _,
+                            z: *z as _,
                         })
                         .collect::<Vec<_>>(),
                     tex_vertices: mesh
@@ -374,11 +770,15 @@ fn run_app(matches: ArgMatches) {
                             w: 0.0,
                         })
                         .collect::<Vec<_>>(),
-                    normals: vec![Vertex {
-                        x: 0.0,
-                        y: 0.0,
-                        z: 1.0,
-                    }],
+                    normals: heightfield
+                        .normals
+                        .iter()
+                        .map(|[x, y, z]| Vertex {
+                            x: *x as _,
+                            y: *y as _,
+                            z: *z as _,
+                        })
+                        .collect::<Vec<_>>(),
                     geometry: vec![Geometry {
                         material_name: None,
                         shapes: mesh
@@ -386,9 +786,9 @@ fn run_app(matches: ArgMatches) {
                             .iter()
                             .map(|t| Shape {
                                 primitive: Primitive::Triangle(
-                                    (t.a, Some(t.a), Some(0)),
-                                    (t.b, Some(t.b), Some(0)),
-                                    (t.c, Some(t.c), Some(0)),
+                                    (t.a, Some(t.a), Some(t.a)),
+                                    (t.b, Some(t.b), Some(t.b)),
+                                    (t.c, Some(t.c), Some(t.c)),
                                 ),
                                 groups: vec![],
                                 smoothing_groups: vec![],
@@ -402,15 +802,78 @@ fn run_app(matches: ArgMatches) {
                 };
                 obj_exporter::export_to_file(&objects, output).expect("Cannot save mesh file");
             } else if png {
-                let mut image = DynamicImage::ImageRgba8(image.to_rgba());
+                let mut image = DynamicImage::ImageRgba8(image.to_rgba8());
                 apply_mesh_on_map(&mut image, &mesh);
                 image.save(output).expect("Cannot save output image");
             }
         }
+        ("stats", Some(matches)) => print!("{}", stats_report(matches)),
         _ => unreachable!(),
     }
 }
 
+fn stats_report(matches: &ArgMatches) -> String {
+    let input = matches.value_of("input").unwrap();
+    let mesh = matches.value_of("mesh").unwrap();
+    let json = matches.is_present("json");
+    let density_source = match matches.value_of("density-source").unwrap() {
+        "luma" => ImageDensitySource::Luma,
+        "luma-alpha" => ImageDensitySource::LumaAlpha,
+        "red" => ImageDensitySource::Red,
+        "green" => ImageDensitySource::Green,
+        "blue" => ImageDensitySource::Blue,
+        "alpha" => ImageDensitySource::Alpha,
+        id => panic!("Unsupported value: {}", id),
+    };
+    let scale = matches
+        .value_of("scale")
+        .unwrap()
+        .parse::<usize>()
+        .expect("Could not parse integer");
+    let visibility_threshold = matches
+        .value_of("visibility-threshold")
+        .unwrap()
+        .parse::<Scalar>()
+        .expect("Could not parse number");
+    let settings = GenerateDensityImageSettings {
+        density_source,
+        scale,
+        ..Default::default()
+    };
+    let image = image::open(input).expect("Cannot open input image");
+    let map =
+        generate_densitymap_from_image(image, &settings).expect("Cannot produce density map image");
+    let contents = read_to_string(mesh).expect("Could not read mesh file");
+    // YAML parser accepts JSON too, but JSON one is faster.
+    let mesh = serde_json::from_str::<DensityMesh>(&contents)
+        .or_else(|_| serde_yaml::from_str::<DensityMesh>(&contents))
+        .expect("Could not parse mesh file");
+    let metrics = ApproximationMetrics::new(&mesh, &map, visibility_threshold)
+        .expect("Cannot measure density mesh");
+    if json {
+        let contents =
+            serde_json::to_string_pretty(&metrics).expect("Could not serialize JSON report");
+        format!("{}\n", contents)
+    } else {
+        format!(
+            "Pixels: {}\n\
+            Covered pixels: {}\n\
+            Visible pixels: {}\n\
+            RMS error: {}\n\
+            Max error: {}\n\
+            Uncovered visible pixels: {}\n\
+            Covered invisible pixels: {}\n",
+            metrics.pixels,
+            metrics.covered_pixels,
+            metrics.visible_pixels,
+            metrics.rms_error,
+            metrics.max_error,
+            metrics.uncovered_visible_pixels,
+            metrics.covered_invisible_pixels,
+        )
+    }
+}
+
 fn apply_mesh_on_map(image: &mut DynamicImage, mesh: &DensityMesh) {
     for triangle in &mesh.triangles {
         let a = mesh.points[triangle.a];
@@ -558,6 +1021,130 @@ mod tests {
         ]));
     }
 
+    #[test]
+    fn test_extrude() {
+        let output = std::env::temp_dir().join("logo.extrude.json");
+        run_app(make_app().get_matches_from(vec![
+            "density-mesh",
+            "mesh",
+            "-i",
+            "../resources/logo.png",
+            "-o",
+            output.to_str().unwrap(),
+            "--json",
+            "--density-source",
+            "alpha",
+            "--extrude-size",
+            "4",
+            "--extrude-join",
+            "round",
+            "--extrude-rings",
+            "2",
+        ]));
+        let mesh = serde_json::from_str::<DensityMesh>(
+            &std::fs::read_to_string(&output).expect("Could not read mesh"),
+        )
+        .expect("Could not parse mesh");
+        assert_eq!(mesh.falloff.len(), mesh.points.len());
+    }
+
+    #[test]
+    fn test_attributes() {
+        let output = std::env::temp_dir().join("logo.attributes.json");
+        run_app(make_app().get_matches_from(vec![
+            "density-mesh",
+            "mesh",
+            "-i",
+            "../resources/logo.png",
+            "-o",
+            output.to_str().unwrap(),
+            "--json",
+            "--density-source",
+            "alpha",
+            "--attributes",
+            "values,uvs,outline",
+        ]));
+        let mesh = serde_json::from_str::<DensityMesh>(
+            &std::fs::read_to_string(&output).expect("Could not read mesh"),
+        )
+        .expect("Could not parse mesh");
+        assert_eq!(mesh.values.len(), mesh.points.len());
+        assert!(mesh.steepness.is_empty());
+        assert_eq!(mesh.uvs.len(), mesh.points.len());
+        assert_eq!(mesh.outline.len(), mesh.points.len());
+        assert!(mesh.outline.iter().any(|v| *v));
+    }
+
+    #[test]
+    fn test_heightfield() {
+        let output = std::env::temp_dir().join("heightmap.heightfield.obj");
+        run_app(make_app().get_matches_from(vec![
+            "density-mesh",
+            "mesh",
+            "-i",
+            "../resources/heightmap.png",
+            "-o",
+            output.to_str().unwrap(),
+            "--obj",
+            "--points-placement",
+            "error-driven",
+            "--error-tolerance",
+            "0.05",
+            "--keep-invisible-triangles",
+            "--height-scale",
+            "64",
+        ]));
+        let contents = std::fs::read_to_string(&output).expect("Could not read mesh");
+        assert!(contents.lines().any(|line| line.starts_with("vn ")));
+    }
+
+    #[test]
+    fn test_stats() {
+        // top row and left pixel of middle row are visible.
+        let input = std::env::temp_dir().join("stats.png");
+        let alpha = [255, 255, 255, 255, 0, 0, 0, 0, 0];
+        image::RgbaImage::from_fn(3, 3, |x, y| [0, 0, 0, alpha[(y * 3 + x) as usize]].into())
+            .save(&input)
+            .expect("Could not save image");
+        // triangle covering the top-left half of the image.
+        let mesh = std::env::temp_dir().join("stats.json");
+        let contents = serde_json::to_string(&DensityMesh {
+            points: vec![
+                Coord::new(0.0, 0.0),
+                Coord::new(2.0, 0.0),
+                Coord::new(0.0, 2.0),
+            ],
+            triangles: vec![[0, 2, 1].into()],
+            ..Default::default()
+        })
+        .expect("Could not serialize mesh");
+        write(&mesh, contents).expect("Could not write mesh");
+        let matches = make_app().get_matches_from(vec![
+            "density-mesh",
+            "stats",
+            "-i",
+            input.to_str().unwrap(),
+            "-m",
+            mesh.to_str().unwrap(),
+            "--json",
+            "--density-source",
+            "alpha",
+        ]);
+        let report = stats_report(matches.subcommand_matches("stats").unwrap());
+        let report =
+            serde_json::from_str::<serde_json::Value>(&report).expect("Could not parse report");
+        assert_eq!(report["pixels"], 9);
+        assert_eq!(report["covered_pixels"], 6);
+        assert_eq!(report["visible_pixels"], 4);
+        assert_eq!(report["uncovered_visible_pixels"], 0);
+        assert_eq!(report["covered_invisible_pixels"], 2);
+        // mesh height falls from 1 at top edge to 0 at bottom corner, so two covered pixels
+        // in the middle row are off by half.
+        let rms_error = report["rms_error"].as_f64().unwrap();
+        assert!((rms_error - (0.5f64 / 6.0).sqrt()).abs() < 1.0e-5);
+        assert_eq!(report["max_error"], 0.5);
+    }
+
     #[test]
     fn test_live() {
         const BRUSH_SIZE: usize = 64;
@@ -572,12 +1159,10 @@ mod tests {
         ) {
             let half_size = BRUSH_SIZE / 2;
             let x = x
-                .checked_sub(half_size)
-                .unwrap_or(0)
+                .saturating_sub(half_size)
                 .min(generator.map().unscaled_width() - BRUSH_SIZE - 1);
             let y = y
-                .checked_sub(half_size)
-                .unwrap_or(0)
+                .saturating_sub(half_size)
                 .min(generator.map().unscaled_height() - BRUSH_SIZE - 1);
             let data = (0..(BRUSH_SIZE * BRUSH_SIZE))
                 .map(|i| {
@@ -589,9 +1174,9 @@ mod tests {
                     let i = sr * generator.map().unscaled_width() + sc;
                     let v = (generator.map().values()[i] * 255.0) as u8;
                     if additive {
-                        v.checked_add(b).unwrap_or(255)
+                        v.saturating_add(b)
                     } else {
-                        v.checked_sub(b).unwrap_or(0)
+                        v.saturating_sub(b)
                     }
                 })
                 .collect::<Vec<_>>();
@@ -614,7 +1199,7 @@ mod tests {
         let image = DynamicImage::ImageRgba8(
             image::open("../resources/heightmap.png")
                 .expect("Cannot open file")
-                .to_rgba(),
+                .to_rgba8(),
         );
         let settings = GenerateDensityImageSettings::default();
         let map = generate_densitymap_from_image(image.clone(), &settings)
@@ -632,7 +1217,7 @@ mod tests {
         generator
             .process_wait()
             .expect("Cannot process generator changes");
-        for i in (0)..(5) {
+        for i in 0..5 {
             let i = 64 + i * 8;
             paint(&mut generator, i, i, &brush, true, &settings);
             generator
@@ -659,7 +1244,7 @@ mod tests {
         let image = DynamicImage::ImageRgba8(
             image::open("../resources/heightmap.png")
                 .expect("Cannot open file")
-                .to_rgba(),
+                .to_rgba8(),
         );
         let settings = GenerateDensityImageSettings::default();
         let map = generate_densitymap_from_image(image.clone(), &settings)
@@ -684,7 +1269,7 @@ mod tests {
             .process_wait()
             .expect("Cannot process live changes");
         let mut image = DynamicImage::ImageRgba8(
-            generate_image_from_densitymap(generator.map(), false).to_rgba(),
+            generate_image_from_densitymap(generator.map(), false).to_rgba8(),
         );
         apply_mesh_on_map(&mut image, generator.mesh().unwrap());
         image
@@ -693,6 +1278,6 @@ mod tests {
     }
 
     fn image_from_map(map: &DensityMap) -> DynamicImage {
-        DynamicImage::ImageRgba8(generate_image_from_densitymap(map, false).to_rgba())
+        DynamicImage::ImageRgba8(generate_image_from_densitymap(map, false).to_rgba8())
     }
 }
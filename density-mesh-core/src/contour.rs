@@ -0,0 +1,864 @@
+use crate::{coord::Coord, delaunay::orient, map::DensityMap, Scalar};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+/// Crossing of iso-line with the edge between neighbour pixels: `(col, row, vertical)`.
+/// Edge starts at pixel `(col, row)` and goes right or up, when vertical.
+type EdgeKey = (isize, isize, bool);
+
+/// Distance (relative to the pixel size) below which neighbour outline points are merged.
+/// Iso-line crossing edges close to pixels produces such points around the pixel corners.
+const MERGE_DISTANCE: Scalar = 0.1;
+
+/// Maximal ratio of distance between inflated corner and original one to the offset of corner
+/// edges. Sharper convex corners get beveled and concave ones are pulled back.
+const INFLATE_MITER_LIMIT: Scalar = 4.0;
+
+/// Kind of contour.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContourKind {
+    /// Contour surrounds area above the level.
+    Outer,
+    /// Contour surrounds area below the level, placed inside of outer one.
+    Hole,
+}
+
+/// Method of polyline simplification.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SimplificationMethod {
+    /// Keep adding points most distant from simplified polyline, until all of them are within
+    /// tolerance distance.
+    #[default]
+    DouglasPeucker,
+    /// Keep removing points that form triangles of the smallest area with their neighbours,
+    /// until all of them have area greater than tolerance.
+    Visvalingam,
+}
+
+/// Settings of contour simplification.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContourSimplification {
+    /// Simplification method.
+    #[serde(default)]
+    pub method: SimplificationMethod,
+    /// Maximal distance of removed points from simplified polyline when using Douglas-Peucker,
+    /// or minimal area of triangles formed by kept points when using Visvalingam.
+    #[serde(default = "ContourSimplification::default_tolerance")]
+    pub tolerance: Scalar,
+    /// Optional limit of polyline points. Closed contours always keep at least 3 points and
+    /// open ones at least 2. Points added by beveling sharp inflated corners count in the limit,
+    /// unless it is already reached by these minimal point counts.
+    #[serde(default)]
+    pub max_vertices: Option<usize>,
+    /// Move simplified polyline edges outward so the original polyline stays inside of it,
+    /// which keeps all visible pixels covered.
+    #[serde(default)]
+    pub inflate: bool,
+}
+
+impl Default for ContourSimplification {
+    fn default() -> Self {
+        Self {
+            method: SimplificationMethod::default(),
+            tolerance: Self::default_tolerance(),
+            max_vertices: None,
+            inflate: false,
+        }
+    }
+}
+
+impl ContourSimplification {
+    fn default_tolerance() -> Scalar {
+        1.0
+    }
+}
+
+/// Polyline of density map iso-line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Contour {
+    /// Polyline points, with area above the level on their left side (with Y axis pointing up).
+    /// Closed contour does not repeat its first point at the end.
+    pub points: Vec<Coord>,
+    /// Tells if last point connects back to the first one.
+    pub closed: bool,
+    /// Contour kind. Open contours get the kind of the closed outline they were cut from.
+    pub kind: ContourKind,
+}
+
+impl Contour {
+    /// Signed area of the polygon made of contour points: positive for outer contours and
+    /// negative for holes.
+    pub fn signed_area(&self) -> Scalar {
+        signed_area(&self.points)
+    }
+
+    /// Length of the polyline.
+    pub fn length(&self) -> Scalar {
+        let segments = self.points.windows(2).map(|w| (w[1] - w[0]).magnitude());
+        let closing = match (self.closed, self.points.first(), self.points.last()) {
+            (true, Some(first), Some(last)) => (*first - *last).magnitude(),
+            _ => 0.0,
+        };
+        segments.sum::<Scalar>() + closing
+    }
+
+    /// Tells if contour is a hole.
+    pub fn is_hole(&self) -> bool {
+        self.kind == ContourKind::Hole
+    }
+
+    /// Create contour that does not go outside of given bounds.
+    /// Closed contours are clipped by bounds rectangle, points of open ones are clamped.
+    ///
+    /// # Arguments
+    /// * `min` - Minimal bounds coordinate.
+    /// * `max` - Maximal bounds coordinate.
+    pub fn clipped(&self, min: Coord, max: Coord) -> Self {
+        let points = if self.closed {
+            let points = clip_polygon(&self.points, |p| p.x - min.x);
+            let points = clip_polygon(&points, |p| max.x - p.x);
+            let points = clip_polygon(&points, |p| p.y - min.y);
+            let mut points = clip_polygon(&points, |p| max.y - p.y);
+            points.dedup();
+            points
+        } else {
+            self.points
+                .iter()
+                .map(|p| Coord::new(p.x.clamp(min.x, max.x), p.y.clamp(min.y, max.y)))
+                .collect()
+        };
+        Self {
+            points,
+            closed: self.closed,
+            kind: self.kind,
+        }
+    }
+
+    /// Create simplified contour.
+    ///
+    /// # Arguments
+    /// * `simplification` - Simplification settings.
+    ///
+    /// # Returns
+    /// Contour made of subset of points (or their inflated versions).
+    ///
+    /// # Examples
+    /// ```
+    /// use density_mesh_core::prelude::*;
+    ///
+    /// let contour = Contour {
+    ///     points: vec![
+    ///         Coord::new(0.0, 0.0),
+    ///         Coord::new(5.0, -0.1),
+    ///         Coord::new(10.0, 0.0),
+    ///         Coord::new(10.0, 10.0),
+    ///         Coord::new(0.0, 10.0),
+    ///     ],
+    ///     closed: true,
+    ///     kind: ContourKind::Outer,
+    /// };
+    /// let simplified = contour.simplified(&ContourSimplification::default());
+    /// assert_eq!(simplified.points.len(), 4);
+    /// let simplified = contour.simplified(&ContourSimplification {
+    ///     inflate: true,
+    ///     ..Default::default()
+    /// });
+    /// assert!((simplified.points[0] - Coord::new(0.0, -0.1)).magnitude() < 1.0e-4);
+    /// assert!((simplified.points[1] - Coord::new(10.0, -0.1)).magnitude() < 1.0e-4);
+    /// ```
+    pub fn simplified(&self, simplification: &ContourSimplification) -> Self {
+        let min_vertices = if self.closed { 3 } else { 2 };
+        let max_vertices = simplification
+            .max_vertices
+            .unwrap_or(usize::MAX)
+            .max(min_vertices);
+        let simplify = |max_vertices| {
+            if self.points.len() <= min_vertices {
+                (0..self.points.len()).collect::<Vec<_>>()
+            } else {
+                match simplification.method {
+                    SimplificationMethod::DouglasPeucker => douglas_peucker(
+                        &self.points,
+                        self.closed,
+                        simplification.tolerance,
+                        max_vertices,
+                    ),
+                    SimplificationMethod::Visvalingam => visvalingam(
+                        &self.points,
+                        self.closed,
+                        simplification.tolerance,
+                        max_vertices,
+                    ),
+                }
+            }
+        };
+        let points = if simplification.inflate {
+            // beveled corners add points, so budget of kept points shrinks until result fits.
+            let mut budget = max_vertices;
+            loop {
+                let points = inflate(&self.points, &simplify(budget), self.closed);
+                if points.len() <= max_vertices || budget <= min_vertices {
+                    break points;
+                }
+                budget = budget
+                    .saturating_sub(points.len() - max_vertices)
+                    .max(min_vertices);
+            }
+        } else {
+            simplify(max_vertices)
+                .into_iter()
+                .map(|i| self.points[i])
+                .collect()
+        };
+        Self {
+            points,
+            closed: self.closed,
+            kind: self.kind,
+        }
+    }
+}
+
+/// Find iso-lines of density map area with values above given level, using marching squares.
+/// Vertices are interpolated between pixels, so they lay where map values cross the level.
+///
+/// # Arguments
+/// * `map` - Density map.
+/// * `level` - Iso-line level.
+/// * `close_at_borders` - If `true`, area outside of the map is treated as being below the
+///   level, so outlines touching map borders are closed along them. Otherwise outlines are cut
+///   where they reach map borders, producing open polylines.
+///
+/// # Returns
+/// Contours of area above the level.
+///
+/// # Examples
+/// ```
+/// use density_mesh_core::prelude::*;
+///
+/// #[rustfmt::skip]
+/// let map = DensityMap::new(4, 4, 1, vec![
+///     0, 0, 0, 0,
+///     0, 255, 255, 0,
+///     0, 255, 255, 0,
+///     0, 0, 0, 0,
+/// ]).unwrap();
+/// let contours = extract_contours(&map, 0.5, true);
+/// assert_eq!(contours.len(), 1);
+/// assert!(contours[0].closed);
+/// assert_eq!(contours[0].kind, ContourKind::Outer);
+/// assert_eq!(contours[0].signed_area(), 3.5);
+///
+/// #[rustfmt::skip]
+/// let map = DensityMap::new(3, 3, 1, vec![
+///     255, 0, 0,
+///     255, 0, 0,
+///     255, 0, 0,
+/// ]).unwrap();
+/// let contours = extract_contours(&map, 0.5, false);
+/// assert_eq!(contours.len(), 1);
+/// assert!(!contours[0].closed);
+/// assert_eq!(
+///     contours[0].points,
+///     vec![Coord::new(0.0, 0.0), Coord::new(0.5, 0.0), Coord::new(0.5, 2.0), Coord::new(0.0, 2.0)],
+/// );
+/// ```
+pub fn extract_contours(map: &DensityMap, level: Scalar, close_at_borders: bool) -> Vec<Contour> {
+    let width = map.unscaled_width() as isize;
+    let height = map.unscaled_height() as isize;
+    let scale = map.scale().max(1) as Scalar;
+    let values = map.values();
+    let value = |col: isize, row: isize| {
+        if col >= 0 && row >= 0 && col < width && row < height {
+            Some(values[(row * width + col) as usize])
+        } else {
+            None
+        }
+    };
+    let is_inside = |col: isize, row: isize| value(col, row).map(|v| v > level).unwrap_or(false);
+
+    let mut next = HashMap::<EdgeKey, EdgeKey>::new();
+    for row in -1..height {
+        for col in -1..width {
+            // cell corners and edges in counter-clockwise order.
+            let corners = [
+                (col, row),
+                (col + 1, row),
+                (col + 1, row + 1),
+                (col, row + 1),
+            ];
+            let edges = [
+                (col, row, false),
+                (col + 1, row, true),
+                (col, row + 1, false),
+                (col, row, true),
+            ];
+            let inside = [
+                is_inside(corners[0].0, corners[0].1),
+                is_inside(corners[1].0, corners[1].1),
+                is_inside(corners[2].0, corners[2].1),
+                is_inside(corners[3].0, corners[3].1),
+            ];
+            let exits = (0..4)
+                .filter(|i| inside[*i] && !inside[(*i + 1) % 4])
+                .collect::<Vec<_>>();
+            let enters = (0..4)
+                .filter(|i| !inside[*i] && inside[(*i + 1) % 4])
+                .collect::<Vec<_>>();
+            if exits.len() == 2 {
+                // saddle is resolved by the average of corners values.
+                let center = corners
+                    .iter()
+                    .map(|(c, r)| value(*c, *r).unwrap_or(0.0))
+                    .sum::<Scalar>()
+                    / 4.0;
+                let joined = center > level;
+                for exit in exits {
+                    let enter = if joined {
+                        (exit + 1) % 4
+                    } else {
+                        (exit + 3) % 4
+                    };
+                    next.insert(edges[exit], edges[enter]);
+                }
+            } else if let (Some(exit), Some(enter)) = (exits.first(), enters.first()) {
+                next.insert(edges[*exit], edges[*enter]);
+            }
+        }
+    }
+
+    // position of iso-line crossing and information if it was moved to the map border.
+    let position = |(col, row, vertical): EdgeKey| {
+        let (tc, tr) = if vertical {
+            (col, row + 1)
+        } else {
+            (col + 1, row)
+        };
+        let from = Coord::new(col as Scalar * scale, row as Scalar * scale);
+        let to = Coord::new(tc as Scalar * scale, tr as Scalar * scale);
+        match (value(col, row), value(tc, tr)) {
+            (Some(a), Some(b)) => {
+                let factor = if (b - a).abs() > 0.0 {
+                    ((level - a) / (b - a)).clamp(0.0, 1.0)
+                } else {
+                    0.5
+                };
+                (from + (to - from) * factor, false)
+            }
+            // outline goes along pixels on the map border.
+            (Some(_), None) => (from, true),
+            _ => (to, true),
+        }
+    };
+
+    let mut keys = next.keys().copied().collect::<Vec<_>>();
+    keys.sort_unstable();
+    let merge_distance = scale * MERGE_DISTANCE;
+    let mut result = vec![];
+    for start in keys {
+        let mut current = match next.remove(&start) {
+            Some(current) => current,
+            None => continue,
+        };
+        let mut points = vec![position(start)];
+        while current != start {
+            points.push(position(current));
+            current = match next.remove(&current) {
+                Some(current) => current,
+                None => break,
+            };
+        }
+        let closed = points.iter().map(|(p, _)| *p).collect::<Vec<_>>();
+        let closed = simplify_collinear(merge_close(closed, merge_distance, true), true);
+        if closed.len() < 3 {
+            continue;
+        }
+        let kind = if signed_area(&closed) >= 0.0 {
+            ContourKind::Outer
+        } else {
+            ContourKind::Hole
+        };
+        if close_at_borders || points.iter().all(|(_, border)| !border) {
+            result.push(Contour {
+                points: closed,
+                closed: true,
+                kind,
+            });
+            continue;
+        }
+        // open polylines start where outline leaves map border and end where it comes back.
+        let count = points.len();
+        for first in (0..count).filter(|i| points[*i].1 && !points[(*i + 1) % count].1) {
+            let mut polyline = vec![points[first].0];
+            for i in 1..count {
+                let (point, border) = points[(first + i) % count];
+                polyline.push(point);
+                if border {
+                    break;
+                }
+            }
+            let polyline = simplify_collinear(merge_close(polyline, merge_distance, false), false);
+            if polyline.len() >= 2 {
+                result.push(Contour {
+                    points: polyline,
+                    closed: false,
+                    kind,
+                });
+            }
+        }
+    }
+    result
+}
+
+fn signed_area(points: &[Coord]) -> Scalar {
+    let count = points.len();
+    (0..count)
+        .map(|i| {
+            let a = points[i];
+            let b = points[(i + 1) % count];
+            a.x as f64 * b.y as f64 - b.x as f64 * a.y as f64
+        })
+        .sum::<f64>() as Scalar
+        * 0.5
+}
+
+/// Remove points which are too close to the previous one. Ends of open polyline are kept.
+fn merge_close(points: Vec<Coord>, distance: Scalar, closed: bool) -> Vec<Coord> {
+    let sqr_distance = distance * distance;
+    let last = points.last().copied();
+    let mut result: Vec<Coord> = Vec::with_capacity(points.len());
+    for point in points {
+        if result
+            .last()
+            .map(|last| (point - *last).sqr_magnitude() >= sqr_distance)
+            .unwrap_or(true)
+        {
+            result.push(point);
+        }
+    }
+    if closed {
+        while result.len() > 1
+            && (result[0] - result[result.len() - 1]).sqr_magnitude() < sqr_distance
+        {
+            result.pop();
+        }
+    } else if let Some(last) = last {
+        if result.last() != Some(&last) {
+            if result.len() > 1 {
+                result.pop();
+            }
+            result.push(last);
+        }
+    }
+    result
+}
+
+/// Remove duplicated and collinear points. Ends of open polyline are kept.
+fn simplify_collinear(mut points: Vec<Coord>, closed: bool) -> Vec<Coord> {
+    points.dedup();
+    while closed && points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    loop {
+        let count = points.len();
+        if count < 3 {
+            return points;
+        }
+        let result = (0..count)
+            .filter(|i| {
+                if !closed && (*i == 0 || *i == count - 1) {
+                    return true;
+                }
+                let prev = points[(i + count - 1) % count];
+                let next = points[(i + 1) % count];
+                orient(prev, points[*i], next) != 0.0
+            })
+            .map(|i| points[i])
+            .collect::<Vec<_>>();
+        if result.len() == count {
+            return result;
+        }
+        points = result;
+    }
+}
+
+/// Clip closed polygon with half-plane, using Sutherland-Hodgman algorithm.
+///
+/// # Arguments
+/// * `points` - Polygon points.
+/// * `distance` - Signed distance of point to the half-plane border, positive inside.
+fn clip_polygon<F>(points: &[Coord], distance: F) -> Vec<Coord>
+where
+    F: Fn(Coord) -> Scalar,
+{
+    let count = points.len();
+    let mut result = Vec::with_capacity(count + 2);
+    for i in 0..count {
+        let a = points[i];
+        let b = points[(i + 1) % count];
+        let (da, db) = (distance(a), distance(b));
+        if da >= 0.0 {
+            result.push(a);
+        }
+        if (da >= 0.0) != (db >= 0.0) {
+            result.push(a + (b - a) * (da / (da - db)));
+        }
+    }
+    result
+}
+
+/// Distance of point from the segment.
+fn segment_distance(a: Coord, b: Coord, point: Coord) -> Scalar {
+    let ab = b - a;
+    let sqr_length = ab.sqr_magnitude();
+    if sqr_length <= 0.0 {
+        return (point - a).magnitude();
+    }
+    let factor = ((point - a).dot(ab) / sqr_length).clamp(0.0, 1.0);
+    (point - (a + ab * factor)).magnitude()
+}
+
+/// Points between `from` and `to` (exclusive), wrapping around closed polyline.
+fn span(from: usize, to: usize, count: usize) -> impl Iterator<Item = usize> {
+    let to = if to <= from { to + count } else { to };
+    (from + 1..to).map(move |i| i % count)
+}
+
+/// Top-down Douglas-Peucker simplification that splits the worst segment first, so it can stop
+/// at vertex budget.
+///
+/// # Returns
+/// Sorted indices of kept points.
+fn douglas_peucker(
+    points: &[Coord],
+    closed: bool,
+    tolerance: Scalar,
+    max_vertices: usize,
+) -> Vec<usize> {
+    let count = points.len();
+    let farthest = |from: usize, to: usize| {
+        span(from, to, count)
+            .map(|i| (segment_distance(points[from], points[to], points[i]), i))
+            .fold(None, |result: Option<(Scalar, usize)>, item| match result {
+                Some(result) if result.0 >= item.0 => Some(result),
+                _ => Some(item),
+            })
+    };
+    let mut kept = vec![0];
+    let mut queue = BinaryHeap::new();
+    let push = |queue: &mut BinaryHeap<(u32, usize, usize, usize)>, from, to| {
+        if let Some((distance, i)) = farthest(from, to) {
+            queue.push((distance.to_bits(), i, from, to));
+        }
+    };
+    if closed {
+        // closed polyline starts as segment to the point most distant from the first one.
+        let far = (1..count)
+            .max_by(|a, b| {
+                let da = (points[*a] - points[0]).sqr_magnitude();
+                let db = (points[*b] - points[0]).sqr_magnitude();
+                da.total_cmp(&db)
+            })
+            .unwrap_or(1);
+        kept.push(far);
+        push(&mut queue, 0, far);
+        push(&mut queue, far, 0);
+    } else {
+        kept.push(count - 1);
+        push(&mut queue, 0, count - 1);
+    }
+    let min_vertices = if closed { 3 } else { 2 };
+    while let Some((distance, i, from, to)) = queue.pop() {
+        if kept.len() >= max_vertices
+            || (kept.len() >= min_vertices && Scalar::from_bits(distance) <= tolerance)
+        {
+            break;
+        }
+        kept.push(i);
+        push(&mut queue, from, i);
+        push(&mut queue, i, to);
+    }
+    kept.sort_unstable();
+    kept
+}
+
+/// Visvalingam-Whyatt simplification.
+///
+/// # Returns
+/// Sorted indices of kept points.
+fn visvalingam(
+    points: &[Coord],
+    closed: bool,
+    tolerance: Scalar,
+    max_vertices: usize,
+) -> Vec<usize> {
+    let count = points.len();
+    let mut prev = (0..count)
+        .map(|i| (i + count - 1) % count)
+        .collect::<Vec<_>>();
+    let mut next = (0..count).map(|i| (i + 1) % count).collect::<Vec<_>>();
+    let mut versions = vec![0; count];
+    let mut removed = vec![false; count];
+    let is_fixed = |i: usize| !closed && (i == 0 || i == count - 1);
+    let area = |a: usize, b: usize, c: usize| {
+        orient(points[a], points[b], points[c]).abs() as Scalar * 0.5
+    };
+    let mut queue = BinaryHeap::new();
+    for i in (0..count).filter(|i| !is_fixed(*i)) {
+        queue.push(Reverse((area(prev[i], i, next[i]).to_bits(), i, 0)));
+    }
+    let min_vertices = if closed { 3 } else { 2 };
+    let mut remaining = count;
+    while let Some(Reverse((area_bits, i, version))) = queue.pop() {
+        if removed[i] || versions[i] != version {
+            continue;
+        }
+        if remaining <= min_vertices
+            || (remaining <= max_vertices && Scalar::from_bits(area_bits) > tolerance)
+        {
+            break;
+        }
+        removed[i] = true;
+        remaining -= 1;
+        let (p, n) = (prev[i], next[i]);
+        next[p] = n;
+        prev[n] = p;
+        for j in [p, n] {
+            if !is_fixed(j) {
+                versions[j] += 1;
+                queue.push(Reverse((
+                    area(prev[j], j, next[j]).to_bits(),
+                    j,
+                    versions[j],
+                )));
+            }
+        }
+    }
+    (0..count).filter(|i| !removed[*i]).collect()
+}
+
+/// Offset simplified polyline edges outward (to their right side), each by the distance of the
+/// most distant original point it replaces. Corners sharper than miter limit get beveled.
+///
+/// # Arguments
+/// * `points` - Original polyline points.
+/// * `kept` - Sorted indices of simplified polyline points.
+/// * `closed` - Tells if polyline is closed.
+fn inflate(points: &[Coord], kept: &[usize], closed: bool) -> Vec<Coord> {
+    let count = kept.len();
+    if count < 2 {
+        return kept.iter().map(|i| points[*i]).collect();
+    }
+    let edges = if closed { count } else { count - 1 };
+    // offset lines of edges: (origin, direction, offset).
+    let lines = (0..edges)
+        .map(|e| {
+            let from = kept[e];
+            let to = kept[(e + 1) % count];
+            let (a, b) = (points[from], points[to]);
+            let direction = (b - a).normalized();
+            let offset = span(from, to, points.len())
+                .map(|i| {
+                    let p = points[i] - a;
+                    // cross product sign tells which side point lays on.
+                    p.y * direction.x - p.x * direction.y
+                })
+                .fold(0.0, |result: Scalar, v| result.max(-v));
+            (a + direction.right() * offset, direction, offset)
+        })
+        .collect::<Vec<_>>();
+    let incoming = |v: usize| {
+        if v > 0 || closed {
+            Some((v + edges - 1) % edges)
+        } else {
+            None
+        }
+    };
+    let outgoing = |v: usize| if v < edges { Some(v) } else { None };
+    let corners = (0..count)
+        .map(|v| {
+            let point = points[kept[v]];
+            match (incoming(v).map(|e| lines[e]), outgoing(v).map(|e| lines[e])) {
+                (Some((oa, da, _)), Some((ob, db, _))) => {
+                    let cross = da.x * db.y - da.y * db.x;
+                    if cross.abs() > 1.0e-4 {
+                        let t = ((ob - oa).x * db.y - (ob - oa).y * db.x) / cross;
+                        oa + da * t
+                    } else {
+                        // nearly parallel edges share offset point.
+                        let pa = oa + da * (point - oa).dot(da);
+                        let pb = ob + db * (point - ob).dot(db);
+                        (pa + pb) * 0.5
+                    }
+                }
+                (Some((o, d, _)), None) | (None, Some((o, d, _))) => o + d * (point - o).dot(d),
+                (None, None) => point,
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut result = Vec::with_capacity(count);
+    for v in 0..count {
+        let point = points[kept[v]];
+        let corner = match (incoming(v).map(|e| lines[e]), outgoing(v).map(|e| lines[e])) {
+            (Some(a), Some(b)) => Some((a, b)),
+            _ => None,
+        };
+        let offset = corner.map(|(a, b)| a.2.max(b.2)).unwrap_or_default();
+        let miter = corners[v] - point;
+        let length = miter.magnitude();
+        let limit = offset * INFLATE_MITER_LIMIT;
+        let ((oa, da, _), (ob, db, _)) = match corner {
+            Some(corner) if length > limit => corner,
+            _ => {
+                result.push(corners[v]);
+                continue;
+            }
+        };
+        let normal = (da.right() + db.right()).normalized();
+        if miter.dot(normal) <= 0.0 {
+            // concave corner is only pulled back.
+            result.push(point + miter * (limit / length));
+            continue;
+        }
+        // convex corner gets beveled, with bevel edge placed beyond all original points.
+        let distance = std::iter::once(kept[(v + count - 1) % count])
+            .chain(span(kept[(v + count - 1) % count], kept[v], points.len()))
+            .chain(span(kept[v], kept[(v + 1) % count], points.len()))
+            .chain(std::iter::once(kept[(v + 1) % count]))
+            .map(|i| (points[i] - point).dot(normal))
+            .fold(limit, Scalar::max);
+        for (o, d) in [(oa, da), (ob, db)] {
+            let t = (distance - (o - point).dot(normal)) / d.dot(normal);
+            result.push(o + d * t);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        generator::{
+            test_utils::{corners, ring_map},
+            DensityMeshGenerator,
+        },
+        mesh::{points_placement::PointsPlacement, settings::GenerateDensityMeshSettings},
+    };
+
+    /// Tells if every original point is on the left side of inflated polyline edges.
+    fn is_covered(original: &[Coord], inflated: &Contour) -> bool {
+        let count = inflated.points.len();
+        (0..count).all(|i| {
+            let a = inflated.points[i];
+            let b = inflated.points[(i + 1) % count];
+            original.iter().all(|p| orient(a, b, *p) >= -1.0e-3)
+        })
+    }
+
+    #[test]
+    fn test_inflate_degenerate() {
+        let simplification = ContourSimplification {
+            inflate: true,
+            ..Default::default()
+        };
+        for closed in [false, true] {
+            for count in 0..3 {
+                let contour = Contour {
+                    points: (0..count).map(|i| Coord::new(i as Scalar, 0.0)).collect(),
+                    closed,
+                    kind: ContourKind::Outer,
+                };
+                let simplified = contour.simplified(&simplification);
+                assert_eq!(simplified.points.len(), count);
+                assert!(simplified
+                    .points
+                    .iter()
+                    .all(|p| p.x.is_finite() && p.y.is_finite()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_inflate_miter_limit() {
+        // thin counter-clockwise wedge with 10 degrees apex at origin and bumpy edges.
+        let (sin, cos) = (5.0 as Scalar).to_radians().sin_cos();
+        let mut points = vec![Coord::default()];
+        points.extend((1..=20).map(|i| {
+            let bump = if i % 5 == 2 { 0.5 } else { 0.0 };
+            Coord::new(cos, -sin) * i as Scalar + Coord::new(sin, cos) * -bump
+        }));
+        points.extend((1..=20).rev().map(|i| {
+            let bump = if i % 5 == 2 { 0.5 } else { 0.0 };
+            Coord::new(cos, sin) * i as Scalar + Coord::new(-sin, cos) * bump
+        }));
+        let contour = Contour {
+            points: points.clone(),
+            closed: true,
+            kind: ContourKind::Outer,
+        };
+        assert!(contour.signed_area() > 0.0);
+        let simplified = contour.simplified(&ContourSimplification {
+            max_vertices: Some(4),
+            inflate: true,
+            ..Default::default()
+        });
+        // apex gets beveled into two points.
+        assert_eq!(simplified.points.len(), 4);
+        assert!(is_covered(&points, &simplified));
+        // unlimited miter would place the tip 0.5 / sin(5 degrees) away from the apex.
+        let tip = simplified
+            .points
+            .iter()
+            .map(|p| p.x)
+            .fold(Scalar::INFINITY, Scalar::min);
+        assert!(tip >= -0.5 * INFLATE_MITER_LIMIT - 1.0e-3, "{}", tip);
+    }
+
+    #[test]
+    fn test_tight_polygon() {
+        let map = ring_map(64);
+        let simplification = ContourSimplification {
+            tolerance: 2.0,
+            max_vertices: Some(12),
+            inflate: true,
+            ..Default::default()
+        };
+        let settings = GenerateDensityMeshSettings {
+            points_placement: PointsPlacement::TightPolygon(simplification),
+            ..Default::default()
+        };
+        let contours = extract_contours(&map, settings.visibility_threshold, true);
+        assert_eq!(contours.len(), 2);
+        for contour in &contours {
+            assert!(contour.simplified(&simplification).points.len() <= 12);
+        }
+        let mut generator = DensityMeshGenerator::new(vec![], map.clone(), settings.clone());
+        generator.process_wait().unwrap();
+        let mesh = generator.into_mesh().unwrap();
+        // map corners and simplified contours.
+        assert!(mesh.points.len() <= 4 + 12 * contours.len());
+        let is_inside = |point: Coord| {
+            mesh.triangles.iter().any(|t| {
+                let [a, b, c] = corners(&mesh, t);
+                let sides = [
+                    orient(a, b, point),
+                    orient(b, c, point),
+                    orient(c, a, point),
+                ];
+                sides.iter().all(|s| *s >= -1.0e-4) || sides.iter().all(|s| *s <= 1.0e-4)
+            })
+        };
+        for row in 0..map.unscaled_height() {
+            for col in 0..map.unscaled_width() {
+                let point = Coord::new(col as Scalar, row as Scalar);
+                if map.values()[row * map.unscaled_width() + col] > settings.visibility_threshold {
+                    assert!(is_inside(point), "uncovered: {:?}", point);
+                }
+            }
+        }
+        assert!(!is_inside(Coord::new(32.0, 32.0)));
+    }
+}
@@ -0,0 +1,843 @@
+use crate::{
+    coord::Coord, map::DensityMap, mesh::GenerateDensityMeshError, triangle::Triangle, Scalar,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Squared distance below which inserted point is treated as already existing one.
+const DUPLICATE_SQR_DISTANCE: Scalar = 1.0e-8;
+
+/// Incremental constrained Delaunay triangulation of points placed within rectangular bounds.
+/// First four points are always the bounds corners.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct DelaunayTriangulation {
+    points: Vec<Coord>,
+    /// [[point index]] in counter-clockwise order (with Y axis pointing up).
+    triangles: Vec<[usize; 3]>,
+    /// [[neighbour triangle index]] where neighbour `i` shares edge from vertex `i` to `i + 1`.
+    neighbors: Vec<[Option<usize>; 3]>,
+    /// Constrained edges `(from, to)`, directed so that shape interior is on their left side.
+    #[serde(default)]
+    segments: HashSet<(usize, usize)>,
+    /// Triangle where point location starts.
+    last: usize,
+}
+
+impl DelaunayTriangulation {
+    /// Create triangulation of bounds rectangle.
+    ///
+    /// # Arguments
+    /// * `min` - Minimal bounds coordinate.
+    /// * `max` - Maximal bounds coordinate.
+    pub fn new(min: Coord, max: Coord) -> Self {
+        Self {
+            points: vec![min, Coord::new(max.x, min.y), max, Coord::new(min.x, max.y)],
+            triangles: vec![[0, 1, 2], [0, 2, 3]],
+            neighbors: vec![[None, None, Some(1)], [Some(0), None, None]],
+            segments: HashSet::new(),
+            last: 0,
+        }
+    }
+
+    /// Create triangulation of density map pixels bounds.
+    pub fn from_map(map: &DensityMap) -> Self {
+        let scale = map.scale().max(1);
+        // single row or column maps still need non-flat bounds to triangulate.
+        let max = Coord::new(
+            (map.unscaled_width().saturating_sub(1) * scale).max(1) as Scalar,
+            (map.unscaled_height().saturating_sub(1) * scale).max(1) as Scalar,
+        );
+        Self::new(Coord::default(), max)
+    }
+
+    /// Returns minimal and maximal bounds coordinate.
+    pub fn bounds(&self) -> (Coord, Coord) {
+        (self.points[0], self.points[2])
+    }
+
+    /// Returns triangulation points.
+    pub fn points(&self) -> &[Coord] {
+        &self.points
+    }
+
+    /// Returns triangles in counter-clockwise order (with Y axis pointing up).
+    pub fn triangles(&self) -> &[[usize; 3]] {
+        &self.triangles
+    }
+
+    /// Returns triangles neighbours, where neighbour `i` shares edge from vertex `i` to `i + 1`.
+    pub fn neighbors(&self) -> &[[Option<usize>; 3]] {
+        &self.neighbors
+    }
+
+    /// Consume triangulation into points and triangles (with winding used by generated meshes).
+    pub fn into_mesh(self) -> (Vec<Coord>, Vec<Triangle>) {
+        let triangles = self
+            .triangles
+            .iter()
+            .map(|[a, b, c]| Triangle {
+                a: *a,
+                b: *c,
+                c: *b,
+            })
+            .collect();
+        (self.points, triangles)
+    }
+
+    /// Tells if edge between given points is constrained.
+    pub fn is_constrained(&self, from: usize, to: usize) -> bool {
+        self.segments.contains(&(from, to)) || self.segments.contains(&(to, from))
+    }
+
+    /// Tells which triangles are inside of the shape enclosed by constrained edges.
+    /// Area on the left side of any constrained edge is inside, so overlapping shapes are merged.
+    ///
+    /// # Arguments
+    /// * `fallback` - Tells if point is inside, used for areas not touching any constrained edge.
+    pub fn inside_triangles<F>(&self, fallback: F) -> Vec<bool>
+    where
+        F: Fn(Coord) -> bool,
+    {
+        let mut result = vec![false; self.triangles.len()];
+        let mut visited = vec![false; self.triangles.len()];
+        for start in 0..self.triangles.len() {
+            if visited[start] {
+                continue;
+            }
+            // flood area bounded by constrained edges.
+            visited[start] = true;
+            let mut area = vec![start];
+            let mut stack = vec![start];
+            let mut inside = None;
+            while let Some(current) = stack.pop() {
+                let triangle = self.triangles[current];
+                for i in 0..3 {
+                    let from = triangle[i];
+                    let to = triangle[(i + 1) % 3];
+                    if self.segments.contains(&(from, to)) {
+                        inside = Some(true);
+                    } else if self.segments.contains(&(to, from)) {
+                        inside = inside.or(Some(false));
+                    } else if let Some(neighbor) = self.neighbors[current][i] {
+                        if !visited[neighbor] {
+                            visited[neighbor] = true;
+                            area.push(neighbor);
+                            stack.push(neighbor);
+                        }
+                    }
+                }
+            }
+            let inside = inside.unwrap_or_else(|| {
+                let [a, b, c] = self.triangles[start];
+                fallback((self.points[a] + self.points[b] + self.points[c]) / 3.0)
+            });
+            for index in area {
+                result[index] = inside;
+            }
+        }
+        result
+    }
+
+    /// Returns index of triangle that contains given point or `None` if point is out of bounds.
+    pub fn locate(&self, point: Coord) -> Option<usize> {
+        let mut current = self.last.min(self.triangles.len() - 1);
+        'walk: for _ in 0..self.triangles.len() {
+            let triangle = self.triangles[current];
+            for i in 0..3 {
+                let a = self.points[triangle[i]];
+                let b = self.points[triangle[(i + 1) % 3]];
+                if orient(a, b, point) < 0.0 {
+                    match self.neighbors[current][i] {
+                        Some(next) => {
+                            current = next;
+                            continue 'walk;
+                        }
+                        None => return None,
+                    }
+                }
+            }
+            return Some(current);
+        }
+        // walk might loop on degenerate triangles so fallback to exhaustive search.
+        (0..self.triangles.len()).find(|index| {
+            let triangle = self.triangles[*index];
+            (0..3).all(|i| {
+                let a = self.points[triangle[i]];
+                let b = self.points[triangle[(i + 1) % 3]];
+                orient(a, b, point) >= 0.0
+            })
+        })
+    }
+
+    /// Insert point into triangulation.
+    ///
+    /// # Arguments
+    /// * `point` - Inserted point.
+    ///
+    /// # Returns
+    /// `(point index, [(changed triangle index, replaced triangle index)])` or `None` if point
+    /// is out of bounds. Replaced triangles are the ones that were occupying the area of changed
+    /// triangles before insertion. When point already exists, its index is returned with no
+    /// changed triangles.
+    pub fn insert(&mut self, point: Coord) -> Option<(usize, Vec<(usize, usize)>)> {
+        let seed = self.locate(point)?;
+        for index in self.triangles[seed].iter() {
+            if (self.points[*index] - point).sqr_magnitude() < DUPLICATE_SQR_DISTANCE {
+                return Some((*index, vec![]));
+            }
+        }
+        let index = self.points.len();
+        self.points.push(point);
+        let (cavity, split) = self.cavity(seed, point);
+        let changed = self.fill_cavity(&cavity, index);
+        for (from, to) in split {
+            if self.segments.remove(&(from, to)) {
+                self.segments.insert((from, index));
+                self.segments.insert((index, to));
+            }
+        }
+        if let Some((first, _)) = changed.first() {
+            self.last = *first;
+        }
+        Some((index, changed))
+    }
+
+    /// Insert constrained edge between existing points.
+    /// Edge gets split on points that lay exactly on it and where it crosses other constrained
+    /// edges.
+    ///
+    /// # Arguments
+    /// * `from` - Start point index.
+    /// * `to` - End point index.
+    ///
+    /// # Returns
+    /// Ok or error when edge (or any of its parts) could not be forced into triangulation.
+    pub fn insert_segment(
+        &mut self,
+        from: usize,
+        to: usize,
+    ) -> Result<(), GenerateDensityMeshError> {
+        let failed =
+            GenerateDensityMeshError::FailedConstrainedEdge(self.points[from], self.points[to]);
+        let mut pending = vec![(from, to)];
+        let mut limit = self.points.len() * 4 + 64;
+        while let Some((from, to)) = pending.pop() {
+            if from == to || limit == 0 {
+                continue;
+            }
+            limit -= 1;
+            match self.force_edge(from, to) {
+                Ok(()) => {
+                    self.segments.insert((from, to));
+                }
+                Err(Some(middle)) => {
+                    pending.push((middle, to));
+                    pending.push((from, middle));
+                }
+                Err(None) => return Err(failed),
+            }
+        }
+        Ok(())
+    }
+
+    /// Make edge between points exist by flipping edges it crosses.
+    ///
+    /// # Returns
+    /// Ok when edge exists, otherwise index of point laying on the edge (if there is any).
+    fn force_edge(&mut self, from: usize, to: usize) -> Result<(), Option<usize>> {
+        let a = self.points[from];
+        let b = self.points[to];
+        let is_between = |p: Coord| (p - a).dot(b - a) > 0.0 && (p - b).dot(a - b) > 0.0;
+        // find triangle around start point which opposite edge is crossed by the segment.
+        let mut start = None;
+        for current in self.triangles_around(from) {
+            let triangle = self.triangles[current];
+            let i = triangle.iter().position(|v| *v == from).unwrap_or_default();
+            let u = triangle[(i + 1) % 3];
+            let v = triangle[(i + 2) % 3];
+            if u == to || v == to {
+                return Ok(());
+            }
+            let (su, sv) = (orient(a, b, self.points[u]), orient(a, b, self.points[v]));
+            if su == 0.0 && is_between(self.points[u]) {
+                return Err(Some(u));
+            }
+            if sv == 0.0 && is_between(self.points[v]) {
+                return Err(Some(v));
+            }
+            if su < 0.0 && sv > 0.0 {
+                start = Some((current, u, v));
+                break;
+            }
+        }
+        let (mut current, mut u, mut v) = start.ok_or(None)?;
+        // walk through triangles crossed by the segment.
+        let mut crossing = VecDeque::new();
+        loop {
+            // segments crossing already constrained edges are split at the crossing point.
+            if self.is_constrained(u, v) {
+                let (pu, pv) = (self.points[u], self.points[v]);
+                let (su, sv) = (orient(a, b, pu), orient(a, b, pv));
+                let crossing = pu + (pv - pu) * (su / (su - sv)) as Scalar;
+                return match self.insert(crossing) {
+                    Some((index, _)) if index != from && index != to => Err(Some(index)),
+                    _ => Err(None),
+                };
+            }
+            crossing.push_back((u, v));
+            let i = self.edge_index(current, u, v).ok_or(None)?;
+            let next = self.neighbors[current][i].ok_or(None)?;
+            let triangle = self.triangles[next];
+            let w = triangle[(self.edge_index(next, v, u).ok_or(None)? + 2) % 3];
+            if w == to {
+                break;
+            }
+            let side = orient(a, b, self.points[w]);
+            if side == 0.0 {
+                return Err(Some(w));
+            } else if side < 0.0 {
+                u = w;
+            } else {
+                v = w;
+            }
+            current = next;
+        }
+        // flip crossed edges until none of them crosses the segment.
+        let mut created = vec![];
+        let mut limit = (crossing.len() + 4) * (crossing.len() + 4) * 4;
+        while let Some((u, v)) = crossing.pop_front() {
+            if limit == 0 {
+                return Err(None);
+            }
+            limit -= 1;
+            let (current, i) = self.find_edge(u, v).ok_or(None)?;
+            let (r, s) = self.opposite(current, i).ok_or(None)?;
+            let (pr, ps) = (self.points[r], self.points[s]);
+            if orient(pr, ps, self.points[u]) * orient(pr, ps, self.points[v]) < 0.0 {
+                self.flip(current, i);
+                if orient(a, b, pr) * orient(a, b, ps) < 0.0
+                    && orient(pr, ps, a) * orient(pr, ps, b) < 0.0
+                {
+                    crossing.push_back((r, s));
+                } else {
+                    created.push((r, s));
+                }
+            } else {
+                crossing.push_back((u, v));
+            }
+        }
+        // restore Delaunay property of new edges.
+        created.retain(|(u, v)| !((*u == from && *v == to) || (*u == to && *v == from)));
+        self.segments.insert((from, to));
+        let legal = self.legalize(created);
+        self.segments.remove(&(from, to));
+        if legal {
+            Ok(())
+        } else {
+            Err(None)
+        }
+    }
+
+    /// Flip edges that are not locally Delaunay, starting from given ones.
+    ///
+    /// # Returns
+    /// `false` if flipping had to stop before all edges got legal.
+    fn legalize(&mut self, mut pending: Vec<(usize, usize)>) -> bool {
+        let mut limit = (pending.len() + 4) * 64;
+        while let Some((u, v)) = pending.pop() {
+            if limit == 0 {
+                return false;
+            }
+            limit -= 1;
+            if self.is_constrained(u, v) {
+                continue;
+            }
+            let (current, i) = match self.find_edge(u, v) {
+                Some(found) => found,
+                None => continue,
+            };
+            let (r, s) = match self.opposite(current, i) {
+                Some(found) => found,
+                None => continue,
+            };
+            let (pu, pv, pr, ps) = (
+                self.points[u],
+                self.points[v],
+                self.points[r],
+                self.points[s],
+            );
+            if in_circle(pu, pv, pr, ps) > 0.0 && orient(pr, ps, pu) * orient(pr, ps, pv) < 0.0 {
+                self.flip(current, i);
+                pending.extend_from_slice(&[(u, s), (s, v), (v, r), (r, u)]);
+            }
+        }
+        true
+    }
+
+    /// Replace edge `i` of triangle with the other diagonal of quad made with its neighbour.
+    fn flip(&mut self, current: usize, i: usize) {
+        let next = match self.neighbors[current][i] {
+            Some(next) => next,
+            None => return,
+        };
+        let triangle = self.triangles[current];
+        let (p, q, r) = (triangle[i], triangle[(i + 1) % 3], triangle[(i + 2) % 3]);
+        let j = match self.edge_index(next, q, p) {
+            Some(j) => j,
+            None => return,
+        };
+        let s = self.triangles[next][(j + 2) % 3];
+        let qr = self.neighbors[current][(i + 1) % 3];
+        let rp = self.neighbors[current][(i + 2) % 3];
+        let ps = self.neighbors[next][(j + 1) % 3];
+        let sq = self.neighbors[next][(j + 2) % 3];
+        self.triangles[current] = [r, p, s];
+        self.neighbors[current] = [rp, ps, Some(next)];
+        self.triangles[next] = [s, q, r];
+        self.neighbors[next] = [sq, qr, Some(current)];
+        if let Some(ps) = ps {
+            self.replace_neighbor(ps, next, current);
+        }
+        if let Some(qr) = qr {
+            self.replace_neighbor(qr, current, next);
+        }
+        self.last = current;
+    }
+
+    fn replace_neighbor(&mut self, triangle: usize, old: usize, new: usize) {
+        if let Some(neighbor) = self.neighbors[triangle]
+            .iter_mut()
+            .find(|n| **n == Some(old))
+        {
+            *neighbor = Some(new);
+        }
+    }
+
+    /// Returns third points of triangle and its neighbour across edge `i`.
+    fn opposite(&self, current: usize, i: usize) -> Option<(usize, usize)> {
+        let triangle = self.triangles[current];
+        let next = self.neighbors[current][i]?;
+        let j = self.edge_index(next, triangle[(i + 1) % 3], triangle[i])?;
+        Some((triangle[(i + 2) % 3], self.triangles[next][(j + 2) % 3]))
+    }
+
+    fn edge_index(&self, triangle: usize, from: usize, to: usize) -> Option<usize> {
+        let triangle = self.triangles[triangle];
+        (0..3).find(|i| triangle[*i] == from && triangle[(*i + 1) % 3] == to)
+    }
+
+    /// Returns triangle with directed edge `from -> to` and index of that edge.
+    fn find_edge(&self, from: usize, to: usize) -> Option<(usize, usize)> {
+        let middle = (self.points[from] + self.points[to]) * 0.5;
+        if let Some(current) = self.locate(middle) {
+            if let Some(i) = self.edge_index(current, from, to) {
+                return Some((current, i));
+            }
+            if let Some(i) = self.edge_index(current, to, from) {
+                let next = self.neighbors[current][i]?;
+                return self.edge_index(next, from, to).map(|i| (next, i));
+            }
+        }
+        (0..self.triangles.len())
+            .find_map(|current| self.edge_index(current, from, to).map(|i| (current, i)))
+    }
+
+    /// Returns triangles that use given point.
+    fn triangles_around(&self, index: usize) -> Vec<usize> {
+        let start = self
+            .locate(self.points[index])
+            .filter(|t| self.triangles[*t].contains(&index))
+            .or_else(|| (0..self.triangles.len()).find(|t| self.triangles[*t].contains(&index)));
+        let start = match start {
+            Some(start) => start,
+            None => return vec![],
+        };
+        let mut result = vec![start];
+        let mut stack = vec![start];
+        while let Some(current) = stack.pop() {
+            for neighbor in self.neighbors[current].iter().flatten() {
+                if self.triangles[*neighbor].contains(&index) && !result.contains(neighbor) {
+                    result.push(*neighbor);
+                    stack.push(*neighbor);
+                }
+            }
+        }
+        result
+    }
+
+    /// Find triangles which circumcircle contains given point and are visible from it.
+    ///
+    /// # Returns
+    /// `([triangle index], [split constrained edge])`
+    fn cavity(&self, seed: usize, point: Coord) -> (Vec<usize>, Vec<(usize, usize)>) {
+        let mut excluded = HashSet::new();
+        loop {
+            let mut cavity = vec![seed];
+            let mut split = vec![];
+            let mut visited = HashSet::new();
+            visited.insert(seed);
+            let mut stack = vec![seed];
+            while let Some(current) = stack.pop() {
+                let triangle = self.triangles[current];
+                for i in 0..3 {
+                    let neighbor = match self.neighbors[current][i] {
+                        Some(neighbor) => neighbor,
+                        None => continue,
+                    };
+                    if visited.contains(&neighbor)
+                        || excluded.contains(&neighbor)
+                        || !self.is_in_circumcircle(neighbor, point)
+                    {
+                        continue;
+                    }
+                    let from = triangle[i];
+                    let to = triangle[(i + 1) % 3];
+                    if self.is_constrained(from, to) {
+                        // constrained edges can be crossed only by splitting them.
+                        let a = self.points[from];
+                        let b = self.points[to];
+                        if !is_on_segment(a, b, point) {
+                            continue;
+                        }
+                        if self.segments.contains(&(from, to)) {
+                            split.push((from, to));
+                        } else {
+                            split.push((to, from));
+                        }
+                    }
+                    visited.insert(neighbor);
+                    cavity.push(neighbor);
+                    stack.push(neighbor);
+                }
+            }
+            // cavity has to be star-shaped from inserted point, otherwise new triangles would
+            // overlap - this might happen only because of numerical errors.
+            let invalid = cavity.iter().copied().find(|current| {
+                *current != seed
+                    && (0..3).any(|i| {
+                        let neighbor = self.neighbors[*current][i];
+                        if neighbor.map(|n| visited.contains(&n)).unwrap_or(false) {
+                            return false;
+                        }
+                        let triangle = self.triangles[*current];
+                        let a = self.points[triangle[i]];
+                        let b = self.points[triangle[(i + 1) % 3]];
+                        let side = orient(a, b, point);
+                        side < 0.0 || (side == 0.0 && neighbor.is_some())
+                    })
+            });
+            match invalid {
+                Some(invalid) => {
+                    excluded.insert(invalid);
+                }
+                None => return (cavity, split),
+            }
+        }
+    }
+
+    /// Replace cavity triangles with fan of triangles around inserted point.
+    fn fill_cavity(&mut self, cavity: &[usize], index: usize) -> Vec<(usize, usize)> {
+        let point = self.points[index];
+        let inside = cavity.iter().copied().collect::<HashSet<_>>();
+        let mut boundary = vec![];
+        for current in cavity {
+            let triangle = self.triangles[*current];
+            for i in 0..3 {
+                let neighbor = self.neighbors[*current][i];
+                if neighbor.map(|n| inside.contains(&n)).unwrap_or(false) {
+                    continue;
+                }
+                let from = triangle[i];
+                let to = triangle[(i + 1) % 3];
+                // point placed on bounds edge splits that edge instead of making flat triangle.
+                if neighbor.is_none() && orient(self.points[from], self.points[to], point) == 0.0 {
+                    continue;
+                }
+                boundary.push((from, to, neighbor, *current));
+            }
+        }
+        let mut slots = cavity.to_vec();
+        while slots.len() < boundary.len() {
+            slots.push(self.triangles.len());
+            self.triangles.push([0; 3]);
+            self.neighbors.push([None; 3]);
+        }
+        slots.truncate(boundary.len());
+        let mut starts = HashMap::with_capacity(boundary.len());
+        let mut ends = HashMap::with_capacity(boundary.len());
+        for ((from, to, neighbor, _), slot) in boundary.iter().zip(slots.iter()) {
+            self.triangles[*slot] = [*from, *to, index];
+            self.neighbors[*slot] = [*neighbor, None, None];
+            if let Some(neighbor) = neighbor {
+                let other = self.triangles[*neighbor];
+                if let Some(i) = (0..3).find(|i| other[*i] == *to && other[(*i + 1) % 3] == *from) {
+                    self.neighbors[*neighbor][i] = Some(*slot);
+                }
+            }
+            starts.insert(*from, *slot);
+            ends.insert(*to, *slot);
+        }
+        for ((from, to, _, _), slot) in boundary.iter().zip(slots.iter()) {
+            self.neighbors[*slot][1] = starts.get(to).copied();
+            self.neighbors[*slot][2] = ends.get(from).copied();
+        }
+        slots
+            .into_iter()
+            .zip(boundary.into_iter().map(|(_, _, _, parent)| parent))
+            .collect()
+    }
+
+    fn is_in_circumcircle(&self, triangle: usize, point: Coord) -> bool {
+        let [a, b, c] = self.triangles[triangle];
+        in_circle(self.points[a], self.points[b], self.points[c], point) > 0.0
+    }
+}
+
+/// Tells if point lays on the segment, allowing rounding errors of computing it.
+fn is_on_segment(a: Coord, b: Coord, point: Coord) -> bool {
+    let length = (b - a).magnitude() as f64;
+    orient(a, b, point).abs() <= length * DUPLICATE_SQR_DISTANCE.sqrt() as f64
+        && (point - a).dot(b - a) > 0.0
+        && (point - b).dot(a - b) > 0.0
+}
+
+/// Positive when `c` lays on the left side of line from `a` to `b`.
+#[inline]
+pub(crate) fn orient(a: Coord, b: Coord, c: Coord) -> f64 {
+    let (ax, ay) = (a.x as f64, a.y as f64);
+    let (bx, by) = (b.x as f64, b.y as f64);
+    let (cx, cy) = (c.x as f64, c.y as f64);
+    (bx - ax) * (cy - ay) - (by - ay) * (cx - ax)
+}
+
+/// Positive when `d` lays inside of circumcircle of counter-clockwise triangle `abc`.
+#[inline]
+pub(crate) fn in_circle(a: Coord, b: Coord, c: Coord, d: Coord) -> f64 {
+    let (adx, ady) = (a.x as f64 - d.x as f64, a.y as f64 - d.y as f64);
+    let (bdx, bdy) = (b.x as f64 - d.x as f64, b.y as f64 - d.y as f64);
+    let (cdx, cdy) = (c.x as f64 - d.x as f64, c.y as f64 - d.y as f64);
+    (adx * adx + ady * ady) * (bdx * cdy - cdx * bdy)
+        + (bdx * bdx + bdy * bdy) * (cdx * ady - adx * cdy)
+        + (cdx * cdx + cdy * cdy) * (adx * bdy - bdx * ady)
+}
+
+/// Center of circle that goes through all triangle corners.
+pub(crate) fn circumcenter(a: Coord, b: Coord, c: Coord) -> Option<Coord> {
+    let (bx, by) = (b.x as f64 - a.x as f64, b.y as f64 - a.y as f64);
+    let (cx, cy) = (c.x as f64 - a.x as f64, c.y as f64 - a.y as f64);
+    let d = 2.0 * (bx * cy - by * cx);
+    if d == 0.0 {
+        return None;
+    }
+    let b2 = bx * bx + by * by;
+    let c2 = cx * cx + cy * cy;
+    let x = (cy * b2 - by * c2) / d;
+    let y = (bx * c2 - cx * b2) / d;
+    Some(Coord::new(
+        (a.x as f64 + x) as Scalar,
+        (a.y as f64 + y) as Scalar,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo random coordinates within given size.
+    fn scattered(count: usize, size: Scalar) -> Vec<Coord> {
+        let mut state = 0x2545_f491u32;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            (state % 10_000) as Scalar / 10_000.0 * size
+        };
+        (0..count).map(|_| Coord::new(next(), next())).collect()
+    }
+
+    /// Triangles are counter-clockwise, neighbours are symmetric and triangles exactly cover
+    /// bounds.
+    fn assert_valid(triangulation: &DelaunayTriangulation) {
+        let mut area = 0.0;
+        for (current, triangle) in triangulation.triangles().iter().enumerate() {
+            let [a, b, c] = triangle.map(|i| triangulation.points()[i]);
+            let orientation = orient(a, b, c);
+            assert!(
+                orientation > 0.0,
+                "triangle {} is not counter-clockwise",
+                current
+            );
+            area += orientation * 0.5;
+            for i in 0..3 {
+                let (from, to) = (triangle[i], triangle[(i + 1) % 3]);
+                if let Some(next) = triangulation.neighbors[current][i] {
+                    let j = triangulation.edge_index(next, to, from).unwrap();
+                    assert_eq!(triangulation.neighbors[next][j], Some(current));
+                }
+            }
+        }
+        let (min, max) = (triangulation.points[0], triangulation.points[2]);
+        let bounds = ((max.x - min.x) * (max.y - min.y)) as f64;
+        assert!(
+            (area - bounds).abs() < bounds * 1.0e-9,
+            "{} != {}",
+            area,
+            bounds
+        );
+    }
+
+    /// Every edge that is not constrained is locally Delaunay.
+    fn assert_delaunay(triangulation: &DelaunayTriangulation) {
+        let points = triangulation.points();
+        for (current, triangle) in triangulation.triangles().iter().enumerate() {
+            for i in 0..3 {
+                let (from, to) = (triangle[i], triangle[(i + 1) % 3]);
+                if triangulation.is_constrained(from, to) {
+                    continue;
+                }
+                if let Some((_, opposite)) = triangulation.opposite(current, i) {
+                    let [a, b, c] = triangle.map(|i| points[i]);
+                    assert!(
+                        in_circle(a, b, c, points[opposite]) <= 1.0e-6,
+                        "edge {:?} is not Delaunay",
+                        (from, to)
+                    );
+                }
+            }
+        }
+    }
+
+    fn has_edge(triangulation: &DelaunayTriangulation, from: usize, to: usize) -> bool {
+        triangulation.triangles().iter().any(|t| {
+            (0..3).any(|i| {
+                (t[i] == from && t[(i + 1) % 3] == to) || (t[i] == to && t[(i + 1) % 3] == from)
+            })
+        })
+    }
+
+    #[test]
+    fn test_insert_keeps_delaunay() {
+        let mut triangulation =
+            DelaunayTriangulation::new(Coord::default(), Coord::new(64.0, 64.0));
+        for point in scattered(200, 64.0) {
+            triangulation.insert(point).unwrap();
+            assert_valid(&triangulation);
+        }
+        assert_delaunay(&triangulation);
+        assert!(triangulation.insert(Coord::new(65.0, 10.0)).is_none());
+    }
+
+    #[test]
+    fn test_force_segment() {
+        let mut triangulation =
+            DelaunayTriangulation::new(Coord::default(), Coord::new(64.0, 64.0));
+        for point in scattered(200, 64.0) {
+            triangulation.insert(point);
+        }
+        let (from, _) = triangulation.insert(Coord::new(1.5, 3.25)).unwrap();
+        let (to, _) = triangulation.insert(Coord::new(62.5, 59.75)).unwrap();
+        let a = triangulation.points()[from];
+        let b = triangulation.points()[to];
+        let crossed = triangulation
+            .triangles()
+            .iter()
+            .filter(|t| {
+                let sides = t.map(|i| orient(a, b, triangulation.points()[i]));
+                sides.iter().any(|s| *s > 0.0) && sides.iter().any(|s| *s < 0.0)
+            })
+            .count();
+        assert!(crossed > 10);
+        triangulation.insert_segment(from, to).unwrap();
+        assert_valid(&triangulation);
+        assert_delaunay(&triangulation);
+        assert!(has_edge(&triangulation, from, to));
+        assert!(triangulation.is_constrained(from, to));
+        // constrained edge survives later insertions, getting split by points laying on it.
+        let (middle, _) = triangulation.insert((a + b) * 0.5).unwrap();
+        for point in scattered(50, 64.0) {
+            triangulation.insert(point + Coord::new(0.01, 0.0));
+        }
+        assert_valid(&triangulation);
+        assert!(triangulation.is_constrained(from, middle));
+        assert!(triangulation.is_constrained(middle, to));
+        assert!(has_edge(&triangulation, from, middle));
+        assert!(has_edge(&triangulation, middle, to));
+    }
+
+    #[test]
+    fn test_collinear_and_duplicate_points() {
+        let mut triangulation =
+            DelaunayTriangulation::new(Coord::default(), Coord::new(10.0, 10.0));
+        let indices = (1..10)
+            .map(|x| {
+                triangulation
+                    .insert(Coord::new(x as Scalar, 5.0))
+                    .unwrap()
+                    .0
+            })
+            .collect::<Vec<_>>();
+        assert_valid(&triangulation);
+        // points on bounds edges split them.
+        triangulation.insert(Coord::new(5.0, 0.0)).unwrap();
+        triangulation.insert(Coord::new(0.0, 5.0)).unwrap();
+        assert_valid(&triangulation);
+        let count = triangulation.points().len();
+        for (x, index) in (1..10).zip(indices.iter()) {
+            let (duplicate, changed) = triangulation.insert(Coord::new(x as Scalar, 5.0)).unwrap();
+            assert_eq!(duplicate, *index);
+            assert!(changed.is_empty());
+        }
+        let (corner, changed) = triangulation.insert(Coord::new(10.0, 10.0)).unwrap();
+        assert_eq!(corner, 2);
+        assert!(changed.is_empty());
+        assert_eq!(triangulation.points().len(), count);
+        assert_valid(&triangulation);
+        assert_delaunay(&triangulation);
+        // segment along collinear points is split on each of them.
+        triangulation
+            .insert_segment(indices[0], indices[8])
+            .unwrap();
+        for pair in indices.windows(2) {
+            assert!(triangulation.is_constrained(pair[0], pair[1]));
+        }
+        assert_valid(&triangulation);
+    }
+
+    #[test]
+    fn test_inside_triangles_with_hole() {
+        let mut triangulation =
+            DelaunayTriangulation::new(Coord::default(), Coord::new(10.0, 10.0));
+        for point in scattered(40, 10.0) {
+            triangulation.insert(point);
+        }
+        // outer loop is counter-clockwise and hole loop is clockwise, so interior is on the left.
+        let outer = [(2.0, 2.0), (8.0, 2.0), (8.0, 8.0), (2.0, 8.0)];
+        let hole = [(4.0, 4.0), (4.0, 6.0), (6.0, 6.0), (6.0, 4.0)];
+        for shape in [outer, hole] {
+            let indices = shape
+                .iter()
+                .map(|(x, y)| triangulation.insert(Coord::new(*x, *y)).unwrap().0)
+                .collect::<Vec<_>>();
+            for i in 0..indices.len() {
+                triangulation
+                    .insert_segment(indices[i], indices[(i + 1) % indices.len()])
+                    .unwrap();
+            }
+        }
+        assert_valid(&triangulation);
+        assert_delaunay(&triangulation);
+        let inside = triangulation.inside_triangles(|_| panic!("every area touches outline"));
+        let mut area = 0.0;
+        for (triangle, inside) in triangulation.triangles().iter().zip(inside) {
+            let [a, b, c] = triangle.map(|i| triangulation.points()[i]);
+            let p = (a + b + c) / 3.0;
+            let in_outer = p.x > 2.0 && p.x < 8.0 && p.y > 2.0 && p.y < 8.0;
+            let in_hole = p.x > 4.0 && p.x < 6.0 && p.y > 4.0 && p.y < 6.0;
+            assert_eq!(inside, in_outer && !in_hole, "centroid {:?}", p);
+            if inside {
+                area += orient(a, b, c) * 0.5;
+            }
+        }
+        assert!((area - 32.0).abs() < 1.0e-6);
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{
+    coord::Coord,
+    delaunay::{orient, DelaunayTriangulation},
+    map::DensityMap,
+    Scalar,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BinaryHeap;
+
+/// Greedy insertion of points with the largest approximation error into Delaunay triangulation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ErrorDrivenInsertion {
+    triangulation: DelaunayTriangulation,
+    /// Point with the largest error of each triangle: `(coordinate, error)`.
+    candidates: Vec<Option<(Coord, Scalar)>>,
+    /// Triangles versions, used to skip outdated queue entries.
+    versions: Vec<usize>,
+    /// [(error bits, triangle index, triangle version)]
+    queue: BinaryHeap<(u32, usize, usize)>,
+    error_tolerance: Scalar,
+    max_points: usize,
+}
+
+impl PartialEq for ErrorDrivenInsertion {
+    fn eq(&self, other: &Self) -> bool {
+        self.triangulation == other.triangulation
+            && self.candidates == other.candidates
+            && self.versions == other.versions
+            && self.queue.as_slice() == other.queue.as_slice()
+            && self.error_tolerance == other.error_tolerance
+            && self.max_points == other.max_points
+    }
+}
+
+impl ErrorDrivenInsertion {
+    /// Create insertion that starts from map corners.
+    ///
+    /// # Arguments
+    /// * `map` - Approximated density map.
+    /// * `points` - Initial points (ones outside of the map are ignored).
+    /// * `error_tolerance` - Maximal accepted approximation error.
+    /// * `max_points` - Limit of points.
+    pub fn new(
+        map: &DensityMap,
+        points: &[Coord],
+        error_tolerance: Scalar,
+        max_points: usize,
+    ) -> Self {
+        let mut triangulation = DelaunayTriangulation::from_map(map);
+        for point in points {
+            triangulation.insert(*point);
+        }
+        let mut result = Self {
+            triangulation,
+            candidates: vec![],
+            versions: vec![],
+            queue: BinaryHeap::new(),
+            error_tolerance,
+            max_points,
+        };
+        let triangles = (0..result.triangulation.triangles().len()).collect::<Vec<_>>();
+        result.update(&triangles, map);
+        result
+    }
+
+    /// Returns number of placed points.
+    pub fn points_count(&self) -> usize {
+        self.triangulation.points().len()
+    }
+
+    /// Insert next point with the largest error.
+    ///
+    /// # Returns
+    /// `true` if there is nothing more to insert.
+    pub fn step(&mut self, map: &DensityMap) -> bool {
+        while let Some((_, triangle, version)) = self.queue.pop() {
+            if self.versions[triangle] != version {
+                continue;
+            }
+            let (point, error) = match self.candidates[triangle] {
+                Some(candidate) => candidate,
+                None => continue,
+            };
+            if error <= self.error_tolerance || self.points_count() >= self.max_points {
+                return true;
+            }
+            match self.triangulation.insert(point) {
+                Some((_, changed)) if !changed.is_empty() => {
+                    let changed = changed.into_iter().map(|(t, _)| t).collect::<Vec<_>>();
+                    self.update(&changed, map);
+                    return false;
+                }
+                _ => self.candidates[triangle] = None,
+            }
+        }
+        true
+    }
+
+    /// Consume insertion into its triangulation.
+    pub fn into_triangulation(self) -> DelaunayTriangulation {
+        self.triangulation
+    }
+
+    fn update(&mut self, triangles: &[usize], map: &DensityMap) {
+        for triangle in triangles {
+            if *triangle >= self.versions.len() {
+                self.versions.resize(*triangle + 1, 0);
+                self.candidates.resize(*triangle + 1, None);
+            }
+            self.versions[*triangle] += 1;
+            let candidate = self.candidate(*triangle, map);
+            self.candidates[*triangle] = candidate;
+            if let Some((_, error)) = candidate {
+                self.queue
+                    .push((error.to_bits(), *triangle, self.versions[*triangle]));
+            }
+        }
+    }
+
+    /// Find map pixel covered by triangle, which value is the most distant from the value
+    /// interpolated between triangle corners.
+    fn candidate(&self, triangle: usize, map: &DensityMap) -> Option<(Coord, Scalar)> {
+        let [a, b, c] = self.triangulation.triangles()[triangle];
+        let points = self.triangulation.points();
+        let (a, b, c) = (points[a], points[b], points[c]);
+        let area = orient(a, b, c);
+        if area <= 0.0 {
+            return None;
+        }
+        let (va, vb, vc) = (
+            map.sample_value(a) as f64,
+            map.sample_value(b) as f64,
+            map.sample_value(c) as f64,
+        );
+        let scale = map.scale().max(1) as Scalar;
+        let width = map.unscaled_width();
+        let height = map.unscaled_height();
+        let values = map.values();
+        let fc = (a.x.min(b.x).min(c.x) / scale).ceil().max(0.0) as usize;
+        let fr = (a.y.min(b.y).min(c.y) / scale).ceil().max(0.0) as usize;
+        let tc = ((a.x.max(b.x).max(c.x) / scale).floor().max(0.0) as usize + 1).min(width);
+        let tr = ((a.y.max(b.y).max(c.y) / scale).floor().max(0.0) as usize + 1).min(height);
+        let mut result = None;
+        let mut max_error = -1.0;
+        for row in fr..tr {
+            for col in fc..tc {
+                let p = Coord::new(col as Scalar * scale, row as Scalar * scale);
+                let wa = orient(b, c, p);
+                let wb = orient(c, a, p);
+                let wc = orient(a, b, p);
+                if wa < 0.0 || wb < 0.0 || wc < 0.0 {
+                    continue;
+                }
+                let value = (va * wa + vb * wb + vc * wc) / area;
+                let error = (values[row * width + col] as f64 - value).abs() as Scalar;
+                if error > max_error {
+                    max_error = error;
+                    result = Some((p, error));
+                }
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generator::test_utils::*, mesh::DensityMesh};
+
+    fn insert_all(map: &DensityMap, error_tolerance: Scalar, max_points: usize) -> DensityMesh {
+        let mut insertion = ErrorDrivenInsertion::new(map, &[], error_tolerance, max_points);
+        while !insertion.step(map) {}
+        let (points, triangles) = insertion.into_triangulation().into_mesh();
+        DensityMesh {
+            points,
+            triangles,
+            ..Default::default()
+        }
+    }
+
+    /// Value interpolated between corners of the first triangle that contains point.
+    fn interpolate(mesh: &DensityMesh, map: &DensityMap, point: Coord) -> Option<Scalar> {
+        mesh.triangles.iter().find_map(|t| {
+            let [a, b, c] = corners(mesh, t);
+            let area = signed_area([a, b, c]);
+            let wa = signed_area([b, c, point]) / area;
+            let wb = signed_area([c, a, point]) / area;
+            let wc = signed_area([a, b, point]) / area;
+            if wa >= -1.0e-5 && wb >= -1.0e-5 && wc >= -1.0e-5 {
+                Some(map.sample_value(a) * wa + map.sample_value(b) * wb + map.sample_value(c) * wc)
+            } else {
+                None
+            }
+        })
+    }
+
+    #[test]
+    fn test_error_tolerance() {
+        let map = wavy_map(48);
+        let mesh = insert_all(&map, 0.05, usize::MAX);
+        assert_valid_cover(&mesh);
+        for row in 0..map.unscaled_height() {
+            for col in 0..map.unscaled_width() {
+                let point = Coord::new(col as Scalar, row as Scalar);
+                let value = interpolate(&mesh, &map, point).unwrap();
+                let error = (value - map.values()[row * map.unscaled_width() + col]).abs();
+                assert!(error <= 0.05 + 1.0e-4, "error {} at {:?}", error, point);
+            }
+        }
+    }
+
+    #[test]
+    fn test_points_budget() {
+        let map = wavy_map(48);
+        let unlimited = insert_all(&map, 0.0, usize::MAX);
+        let budgeted = insert_all(&map, 0.0, 40);
+        assert_eq!(budgeted.points.len(), 40);
+        assert!(budgeted.points.len() < unlimited.points.len());
+        assert_valid_cover(&budgeted);
+    }
+}
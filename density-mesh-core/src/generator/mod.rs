@@ -1,13 +1,41 @@
+mod error_driven;
+mod points_grid;
 pub mod process_status;
 mod processing_change;
+pub mod progress_report;
+mod random;
+mod refinement;
+pub mod stage;
+pub mod strategy;
+#[cfg(test)]
+pub(crate) mod test_utils;
+#[cfg(test)]
+mod tests;
+mod visibility;
 
 use crate::{
+    contour::{extract_contours, Contour},
     coord::Coord,
-    generator::{process_status::ProcessStatus, processing_change::ProcessingChange},
+    delaunay::DelaunayTriangulation,
+    generator::{
+        error_driven::ErrorDrivenInsertion,
+        points_grid::PointsGrid,
+        process_status::ProcessStatus,
+        processing_change::{ProcessingChange, RegionChange, TriangulationCache},
+        progress_report::{ProgressReport, ProgressStage},
+        random::Random,
+        refinement::Refinement,
+        stage::{ProcessingStage, Stages},
+        strategy::{PointScoring, Strategies, TriangleFilter, TriangleSample},
+        visibility::VisibilityTable,
+    },
     map::{DensityMap, DensityMapError},
     mesh::{
-        points_separation::PointsSeparation, settings::GenerateDensityMeshSettings, DensityMesh,
-        GenerateDensityMeshError,
+        extrude::{ExtrudeDirection, ExtrudeJoin, ExtrudeSettings},
+        half_edge::HalfEdgeMesh,
+        points_placement::PointsPlacement,
+        settings::GenerateDensityMeshSettings,
+        DensityMesh, GenerateDensityMeshError,
     },
     triangle::Triangle,
     Scalar,
@@ -16,11 +44,18 @@ use crate::{
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
     time::{Duration, Instant},
 };
 use triangulation::{Delaunay, Point};
 
+/// Maximal angle between neighbour points of round extrude join.
+const ROUND_JOIN_STEP: Scalar = std::f32::consts::PI / 8.0;
+
+/// Extruded points, triangles and points falloff.
+type ExtrudedOutline = (Vec<Coord>, Vec<Triangle>, Vec<Scalar>);
+
 #[cfg(feature = "parallel")]
 macro_rules! into_iter {
     ($v:expr) => {
@@ -35,15 +70,56 @@ macro_rules! into_iter {
     };
 }
 
+#[cfg(feature = "parallel")]
+macro_rules! sort_by {
+    ($v:expr, $f:expr) => {
+        $v.par_sort_by($f)
+    };
+}
+
+#[cfg(not(feature = "parallel"))]
+macro_rules! sort_by {
+    ($v:expr, $f:expr) => {
+        $v.sort_by($f)
+    };
+}
+
+/// Changed map region: `(col, row, width, height)`.
+type MapRegion = (usize, usize, usize, usize);
+
+/// Queued change: `([points], region, settings)`.
+type QueuedChange = (Vec<Coord>, Option<MapRegion>, GenerateDensityMeshSettings);
+
 /// Generate density mesh with region changes.
-/// For now it recalculates mesh from whole density map data.
+/// Map region changes regenerate only the part of the mesh that covers changed region, as long
+/// as they use the same settings as the previous change.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DensityMeshGenerator {
     map: DensityMap,
     mesh: Option<DensityMesh>,
-    /// [([points], settings)]
-    queue: VecDeque<(Vec<Coord>, GenerateDensityMeshSettings)>,
+    queue: VecDeque<QueuedChange>,
     current: Option<ProcessingChange>,
+    /// Change being processed and whether it is processed again after failure.
+    #[serde(default)]
+    processed: Option<(QueuedChange, bool)>,
+    /// Tells if the first queued change is the failed one, queued again.
+    #[serde(default)]
+    retrying: bool,
+    /// Triangulation of published mesh.
+    #[serde(default)]
+    cache: Option<TriangulationCache>,
+    /// Triangulation of processed change, which replaces cache once its mesh gets published.
+    #[serde(default)]
+    pending_cache: Option<TriangulationCache>,
+    /// Visible pixels lookup of current map, rebuilt on demand.
+    #[serde(skip)]
+    visibility: Option<VisibilityTable>,
+    /// Custom strategies are not serialized, so they have to be set again after deserialization.
+    #[serde(skip)]
+    strategies: Strategies,
+    /// Custom stages are not serialized either, unfinished stage is skipped when they are missing.
+    #[serde(skip)]
+    stages: Stages,
 }
 
 impl DensityMeshGenerator {
@@ -58,13 +134,111 @@ impl DensityMeshGenerator {
     /// New generator instance.
     pub fn new(points: Vec<Coord>, map: DensityMap, settings: GenerateDensityMeshSettings) -> Self {
         let mut queue = VecDeque::with_capacity(1);
-        queue.push_back((points, settings));
+        queue.push_back((points, None, settings));
         Self {
             map,
             mesh: None,
             queue,
             current: None,
+            processed: None,
+            retrying: false,
+            cache: None,
+            pending_cache: None,
+            visibility: None,
+            strategies: Strategies::default(),
+            stages: Stages::default(),
+        }
+    }
+
+    /// Replace scoring of point candidates of steepness points placement (by default the
+    /// steepest candidates are placed first). Used by changes processed from now on.
+    ///
+    /// # Arguments
+    /// * `scoring` - Point scoring.
+    ///
+    /// # Examples
+    /// ```
+    /// use density_mesh_core::prelude::*;
+    ///
+    /// let data = (0..256).map(|i| if i % 16 < 8 { 255 } else { 0 }).collect();
+    /// let map = DensityMap::new(16, 16, 1, data).unwrap();
+    /// let settings = GenerateDensityMeshSettings {
+    ///     points_separation: 4.0.into(),
+    ///     ..Default::default()
+    /// };
+    /// let mut generator = DensityMeshGenerator::new(vec![], map, settings);
+    /// // prefer points closer to the map origin.
+    /// generator.set_point_scoring(|point: Coord, _, _| -point.magnitude());
+    /// generator.process_wait().unwrap();
+    /// assert!(generator.mesh().is_some());
+    /// ```
+    pub fn set_point_scoring<T>(&mut self, scoring: T)
+    where
+        T: PointScoring + 'static,
+    {
+        self.strategies.point_scoring = Some(Arc::new(scoring));
+    }
+
+    /// Replace predicate that decides which triangles are kept when invisible triangles get
+    /// removed (by default triangle visibility criterion of settings is used). Used by changes
+    /// processed from now on. Constrained triangulation passes to it only triangles inside of
+    /// the outline, the ones outside are always removed.
+    ///
+    /// # Arguments
+    /// * `filter` - Triangle filter.
+    ///
+    /// # Examples
+    /// ```
+    /// use density_mesh_core::prelude::*;
+    ///
+    /// let data = (0..256).map(|i| if i % 16 < 8 { 255 } else { 0 }).collect();
+    /// let map = DensityMap::new(16, 16, 1, data).unwrap();
+    /// let settings = GenerateDensityMeshSettings {
+    ///     points_separation: 4.0.into(),
+    ///     ..Default::default()
+    /// };
+    /// let mut generator = DensityMeshGenerator::new(vec![], map, settings);
+    /// generator.set_triangle_filter(|triangle: &TriangleSample, _: &GenerateDensityMeshSettings| {
+    ///     let (visible, _, _) = triangle.coverage();
+    ///     visible > 0
+    /// });
+    /// generator.process_wait().unwrap();
+    /// assert!(generator.mesh().is_some());
+    /// ```
+    pub fn set_triangle_filter<T>(&mut self, filter: T)
+    where
+        T: TriangleFilter + 'static,
+    {
+        self.strategies.triangle_filter = Some(Arc::new(filter));
+        // cached visibility was decided by previous filter.
+        self.cache = None;
+        self.pending_cache = None;
+    }
+
+    /// Restore default point scoring and triangle filter.
+    pub fn reset_strategies(&mut self) {
+        if self.strategies.triangle_filter.is_some() {
+            self.cache = None;
+            self.pending_cache = None;
         }
+        self.strategies = Strategies::default();
+    }
+
+    /// Register processing stage, which runs on built mesh after previously registered ones.
+    /// Used by changes processed from now on.
+    ///
+    /// # Arguments
+    /// * `stage` - Processing stage.
+    pub fn add_stage<T>(&mut self, stage: T)
+    where
+        T: ProcessingStage + 'static,
+    {
+        self.stages.0.push(Arc::new(stage));
+    }
+
+    /// Remove all registered processing stages.
+    pub fn clear_stages(&mut self) {
+        self.stages.0.clear();
     }
 
     /// Get inner density map.
@@ -88,33 +262,152 @@ impl DensityMeshGenerator {
 
     /// Get processing progress.
     ///
-    /// # Returns
-    /// `(current, limit, percentage)`
-    pub fn progress(&self) -> (usize, usize, Scalar) {
-        match &self.current {
+    /// # Examples
+    /// ```
+    /// use density_mesh_core::prelude::*;
+    ///
+    /// let data = (0..256).map(|i| if i % 16 < 8 { 255 } else { 0 }).collect();
+    /// let map = DensityMap::new(16, 16, 1, data).unwrap();
+    /// let settings = GenerateDensityMeshSettings {
+    ///     points_separation: 4.0.into(),
+    ///     ..Default::default()
+    /// };
+    /// let mut generator = DensityMeshGenerator::new(vec![], map, settings);
+    /// let report = generator.progress();
+    /// assert_eq!(report.stage, ProgressStage::Idle);
+    /// assert_eq!(report.queued_changes, 1);
+    /// assert_eq!(report.overall_progress, 0.0);
+    /// let mut last = 0.0;
+    /// generator
+    ///     .process_wait_tracked(|report| {
+    ///         assert!(report.overall_progress >= last);
+    ///         last = report.overall_progress;
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(generator.progress().overall_progress, 1.0);
+    /// ```
+    pub fn progress(&self) -> ProgressReport {
+        let queued_changes = self.queue.len();
+        let (stage, current, limit, points, settings) = match &self.current {
             Some(ProcessingChange::FindingPoints {
+                settings,
+                points,
                 progress_current,
                 progress_limit,
                 ..
             }) => (
+                ProgressStage::FindingPoints,
                 *progress_current,
                 *progress_limit,
-                *progress_current as Scalar / *progress_limit as Scalar,
+                points.len(),
+                settings,
             ),
-            Some(ProcessingChange::Triangulate { progress_limit, .. }) => {
-                (*progress_limit, *progress_limit, 1.0)
-            }
-            Some(ProcessingChange::RemoveInvisibleTriangles { progress_limit, .. }) => {
-                (*progress_limit, *progress_limit, 1.0)
+            Some(ProcessingChange::SamplingPoints {
+                settings,
+                points,
+                progress_current,
+                progress_limit,
+                ..
+            }) => (
+                ProgressStage::SamplingPoints,
+                *progress_current,
+                *progress_limit,
+                points.len(),
+                settings,
+            ),
+            Some(ProcessingChange::InsertingPoints {
+                settings,
+                insertion,
+                progress_limit,
+            }) => (
+                ProgressStage::InsertingPoints,
+                insertion.points_count(),
+                *progress_limit,
+                insertion.points_count(),
+                settings,
+            ),
+            Some(ProcessingChange::Triangulate {
+                settings, points, ..
+            }) => (ProgressStage::Triangulating, 0, 1, points.len(), settings),
+            Some(ProcessingChange::Refining {
+                settings,
+                refinement,
+                ..
+            }) => {
+                let (current, limit) = refinement.progress();
+                (
+                    ProgressStage::Refining,
+                    current,
+                    limit,
+                    refinement.points_count(),
+                    settings,
+                )
             }
-            Some(ProcessingChange::Extrude { progress_limit, .. }) => {
-                (*progress_limit, *progress_limit, 1.0)
+            Some(ProcessingChange::RemoveInvisibleTriangles {
+                settings, points, ..
+            }) => (
+                ProgressStage::RemovingInvisibleTriangles,
+                0,
+                1,
+                points.len(),
+                settings,
+            ),
+            Some(ProcessingChange::Extrude {
+                settings, points, ..
+            }) => (ProgressStage::Extruding, 0, 1, points.len(), settings),
+            Some(ProcessingChange::Stage {
+                settings,
+                mesh,
+                stage,
+                step,
+                steps,
+            }) => (
+                ProgressStage::Custom(*stage),
+                *step,
+                *steps,
+                mesh.points.len(),
+                settings,
+            ),
+            None => {
+                return ProgressReport {
+                    queued_changes,
+                    overall_progress: if queued_changes > 0 { 0.0 } else { 1.0 },
+                    ..Default::default()
+                }
             }
-            _ => (0, 0, 0.0),
+        };
+        let stage_progress = if limit > 0 {
+            (current as Scalar / limit as Scalar).min(1.0)
+        } else {
+            0.0
+        };
+        // stages that run for this change, current one might be missing when region change
+        // uses different points placement.
+        let (done, total) = self
+            .planned_stages(settings)
+            .into_iter()
+            .filter(|s| *s != stage)
+            .fold((0.0, stage.weight()), |(done, total), s| {
+                if s.order() < stage.order() {
+                    (done + s.weight(), total + s.weight())
+                } else {
+                    (done, total + s.weight())
+                }
+            });
+        ProgressReport {
+            stage,
+            current,
+            limit,
+            stage_progress,
+            overall_progress: (done + stage.weight() * stage_progress) / total,
+            queued_changes,
+            points,
         }
     }
 
     /// Add map change to the pending queue.
+    /// When processed, only points and triangles around changed region (extended by
+    /// `settings.update_region_margin`) are regenerated.
     ///
     /// # Arguments
     /// * `col` - Density map destination column.
@@ -136,7 +429,71 @@ impl DensityMeshGenerator {
         settings: GenerateDensityMeshSettings,
     ) -> Result<(), DensityMapError> {
         self.map.change(col, row, width, height, data)?;
-        self.queue.push_back((vec![], settings));
+        if let Some(visibility) = &mut self.visibility {
+            visibility.invalidate(row, height);
+        }
+        self.queue
+            .push_back((vec![], Some((col, row, width, height)), settings));
+        Ok(())
+    }
+
+    /// Add map change with 16-bit data to the pending queue.
+    ///
+    /// # Arguments
+    /// * `col` - Density map destination column.
+    /// * `row` - Density map destination row.
+    /// * `width` - Source data unscaled width.
+    /// * `height` - Source data unscaled height.
+    /// * `data` - Source 16-bit data buffer.
+    /// * `settings` - Density mesh generation settings applied for this change.
+    ///
+    /// # Returns
+    /// Ok if successful or density map error.
+    pub fn change_map_u16(
+        &mut self,
+        col: usize,
+        row: usize,
+        width: usize,
+        height: usize,
+        data: Vec<u16>,
+        settings: GenerateDensityMeshSettings,
+    ) -> Result<(), DensityMapError> {
+        self.map.change_u16(col, row, width, height, data)?;
+        if let Some(visibility) = &mut self.visibility {
+            visibility.invalidate(row, height);
+        }
+        self.queue
+            .push_back((vec![], Some((col, row, width, height)), settings));
+        Ok(())
+    }
+
+    /// Add map change with normalized values to the pending queue.
+    ///
+    /// # Arguments
+    /// * `col` - Density map destination column.
+    /// * `row` - Density map destination row.
+    /// * `width` - Source data unscaled width.
+    /// * `height` - Source data unscaled height.
+    /// * `data` - Source normalized values buffer.
+    /// * `settings` - Density mesh generation settings applied for this change.
+    ///
+    /// # Returns
+    /// Ok if successful or density map error.
+    pub fn change_map_scalar(
+        &mut self,
+        col: usize,
+        row: usize,
+        width: usize,
+        height: usize,
+        data: Vec<Scalar>,
+        settings: GenerateDensityMeshSettings,
+    ) -> Result<(), DensityMapError> {
+        self.map.change_scalar(col, row, width, height, data)?;
+        if let Some(visibility) = &mut self.visibility {
+            visibility.invalidate(row, height);
+        }
+        self.queue
+            .push_back((vec![], Some((col, row, width, height)), settings));
         Ok(())
     }
 
@@ -146,194 +503,716 @@ impl DensityMeshGenerator {
     /// Result with process status when ok, otherwise error.
     #[allow(clippy::many_single_char_names)]
     pub fn process(&mut self) -> Result<ProcessStatus, GenerateDensityMeshError> {
-        if let Some(current) = std::mem::replace(&mut self.current, None) {
+        if let Some(current) = self.current.take() {
             match current {
                 ProcessingChange::FindingPoints {
                     settings,
                     mut tries,
                     mut remaining,
                     mut points,
-                    mut progress_current,
+                    mut grid,
+                    region,
                     progress_limit,
+                    ..
                 } => {
-                    if !points.is_empty() {
-                        remaining = into_iter!(remaining)
-                            .filter(|(p1, _, _, lpss)| {
-                                points.iter().all(|p2| (*p2 - *p1).sqr_magnitude() > *lpss)
-                            })
-                            .collect::<Vec<_>>();
-                        if remaining.is_empty() {
-                            self.current = Some(ProcessingChange::Triangulate {
-                                settings,
-                                points,
-                                progress_limit,
-                            });
-                            return Ok(ProcessStatus::InProgress);
+                    // candidates are sorted by score, so the last one has the highest score.
+                    let mut rejected = 0;
+                    while let Some((point, _, _, lpss)) = remaining.pop() {
+                        if grid.has_any_within(point, lpss, &points) {
+                            rejected += 1;
+                            if rejected >= settings.max_step_rejections.max(1) {
+                                break;
+                            }
+                        } else {
+                            grid.insert(points.len(), point);
+                            points.push(point);
+                            break;
                         }
                     }
-                    if let Some((point, _, _, _)) = remaining
-                        .iter()
-                        .max_by(|a, b| a.2.partial_cmp(&b.2).unwrap())
-                    {
-                        points.push(*point);
-                        tries = settings.max_iterations;
-                    } else if tries > 0 {
+                    // when no point can be placed at all, keep trying for `max_iterations` steps.
+                    if remaining.is_empty() && points.is_empty() && tries > 0 {
                         tries -= 1;
                         self.current = Some(ProcessingChange::FindingPoints {
                             settings,
                             tries,
+                            progress_current: progress_limit,
                             remaining,
                             points,
-                            progress_current,
+                            grid,
+                            region,
                             progress_limit,
                         });
-                        return Ok(ProcessStatus::InProgress);
-                    } else {
+                    } else if remaining.is_empty() {
                         self.current = Some(ProcessingChange::Triangulate {
                             settings,
                             points,
+                            region,
+                            progress_limit,
+                        });
+                    } else {
+                        self.current = Some(ProcessingChange::FindingPoints {
+                            settings,
+                            tries,
+                            progress_current: progress_limit - remaining.len(),
+                            remaining,
+                            points,
+                            grid,
+                            region,
                             progress_limit,
                         });
-                        return Ok(ProcessStatus::InProgress);
                     }
-                    progress_current = progress_limit - remaining.len();
-                    self.current = Some(ProcessingChange::FindingPoints {
+                    Ok(ProcessStatus::InProgress)
+                }
+                ProcessingChange::SamplingPoints {
+                    settings,
+                    mut random,
+                    mut remaining,
+                    mut active,
+                    mut points,
+                    mut grid,
+                    progress_limit,
+                    ..
+                } => {
+                    if active.is_empty() {
+                        // start sampling of area not reached yet.
+                        while let Some(point) = remaining.pop() {
+                            let lpss =
+                                Self::separation(self.map.sample_steepness(point), &settings);
+                            if !grid.has_any_within(point, lpss, &points) {
+                                active.push(points.len());
+                                grid.insert(points.len(), point);
+                                points.push(point);
+                                break;
+                            }
+                        }
+                        if active.is_empty() {
+                            self.current = Some(ProcessingChange::Triangulate {
+                                settings,
+                                points,
+                                region: None,
+                                progress_limit,
+                            });
+                            return Ok(ProcessStatus::InProgress);
+                        }
+                    } else if let Some(point) =
+                        self.sample_around(&mut random, &mut active, &points, &grid, &settings)
+                    {
+                        active.push(points.len());
+                        grid.insert(points.len(), point);
+                        points.push(point);
+                    }
+                    self.current = Some(ProcessingChange::SamplingPoints {
                         settings,
-                        tries,
+                        random,
+                        progress_current: progress_limit - remaining.len(),
                         remaining,
+                        active,
                         points,
-                        progress_current,
+                        grid,
                         progress_limit,
                     });
                     Ok(ProcessStatus::InProgress)
                 }
+                ProcessingChange::InsertingPoints {
+                    settings,
+                    mut insertion,
+                    progress_limit,
+                } => {
+                    if insertion.step(&self.map) {
+                        let mut triangulation = insertion.into_triangulation();
+                        if settings.constrain_contour {
+                            let contours =
+                                extract_contours(&self.map, settings.visibility_threshold, true);
+                            Self::constrain_contour(&mut triangulation, &contours)?;
+                        }
+                        Ok(self.delaunay_triangulated(triangulation, settings, progress_limit))
+                    } else {
+                        self.current = Some(ProcessingChange::InsertingPoints {
+                            settings,
+                            insertion,
+                            progress_limit,
+                        });
+                        Ok(ProcessStatus::InProgress)
+                    }
+                }
                 ProcessingChange::Triangulate {
                     settings,
                     points,
+                    region,
                     progress_limit,
                 } => {
-                    let dpoints = points
-                        .iter()
-                        .map(|v| Point::new(v.x, v.y))
-                        .collect::<Vec<_>>();
-                    let triangulation = if let Some(triangulation) = Delaunay::new(&dpoints) {
-                        triangulation
+                    if Self::is_constrained(&settings) || Self::is_refined(&settings) {
+                        let triangulation = self.triangulate_delaunay(&points, &settings)?;
+                        return Ok(self.delaunay_triangulated(
+                            triangulation,
+                            settings,
+                            progress_limit,
+                        ));
+                    }
+                    let (triangles, visibility) = if let Some(region) = region {
+                        Self::triangulate_region(&points, region)?
                     } else {
-                        return Err(GenerateDensityMeshError::FailedTriangulation);
+                        let triangles = Self::triangulate(&points)?;
+                        let visibility = vec![None; triangles.len()];
+                        (triangles, visibility)
                     };
-                    let triangles = triangulation
-                        .dcel
-                        .vertices
-                        .chunks(3)
-                        .map(|t| Triangle {
-                            a: t[0],
-                            b: t[1],
-                            c: t[2],
-                        })
-                        .collect::<Vec<_>>();
-                    if !settings.keep_invisible_triangles {
-                        self.current = Some(ProcessingChange::RemoveInvisibleTriangles {
+                    Ok(self.triangulated(points, triangles, visibility, settings, progress_limit))
+                }
+                ProcessingChange::Refining {
+                    settings,
+                    mut refinement,
+                    progress_limit,
+                } => {
+                    if refinement.step(&self.map) {
+                        let (triangulation, inside) = refinement.into_inner();
+                        Ok(self.finish_triangulation(
+                            triangulation,
+                            inside,
                             settings,
-                            points,
-                            triangles,
                             progress_limit,
-                        });
-                        Ok(ProcessStatus::InProgress)
-                    } else if let Some(size) = settings.extrude_size {
-                        self.current = Some(ProcessingChange::Extrude {
-                            points,
-                            triangles,
-                            size,
+                        ))
+                    } else {
+                        self.current = Some(ProcessingChange::Refining {
+                            settings,
+                            refinement,
                             progress_limit,
                         });
                         Ok(ProcessStatus::InProgress)
-                    } else {
-                        self.mesh = Some(DensityMesh { points, triangles });
-                        Ok(ProcessStatus::MeshChanged)
                     }
                 }
                 ProcessingChange::RemoveInvisibleTriangles {
                     settings,
                     points,
-                    mut triangles,
+                    triangles,
+                    visibility,
                     progress_limit,
                 } => {
-                    triangles = triangles
-                        .into_iter()
-                        .filter(|t| {
-                            Self::is_triangle_visible(
-                                points[t.a],
-                                points[t.b],
-                                points[t.c],
-                                &self.map,
-                                &settings,
-                            )
+                    self.refresh_visibility(&settings);
+                    let table = self.visibility.as_ref().unwrap();
+                    let map = &self.map;
+                    let filter = self.strategies.triangle_filter();
+                    let visibility = into_iter!(0..triangles.len())
+                        .map(|i| {
+                            let t = &triangles[i];
+                            Some(visibility[i].unwrap_or_else(|| {
+                                let corners = [points[t.a], points[t.b], points[t.c]];
+                                let sample = TriangleSample::new(corners, map, table);
+                                filter.is_visible(&sample, &settings)
+                            }))
                         })
                         .collect::<Vec<_>>();
-                    if let Some(size) = settings.extrude_size {
-                        self.current = Some(ProcessingChange::Extrude {
-                            points,
-                            triangles,
-                            size,
-                            progress_limit,
-                        });
-                        Ok(ProcessStatus::InProgress)
-                    } else {
-                        self.mesh = Some(DensityMesh { points, triangles });
-                        Ok(ProcessStatus::MeshChanged)
-                    }
+                    let visible = triangles
+                        .iter()
+                        .zip(visibility.iter())
+                        .filter(|(_, v)| **v == Some(true))
+                        .map(|(t, _)| *t)
+                        .collect::<Vec<_>>();
+                    self.pending_cache = Some(TriangulationCache {
+                        settings: settings.clone(),
+                        points: points.clone(),
+                        triangles,
+                        visibility,
+                    });
+                    Ok(self.complete(points, visible, &settings, progress_limit))
                 }
                 ProcessingChange::Extrude {
                     mut points,
                     mut triangles,
                     size,
+                    settings,
                     ..
                 } => {
-                    let (p, t) = Self::extrude(&points, &triangles, size);
+                    let (p, t, f) = Self::extrude(&points, &triangles, size, &settings.extrude)?;
+                    let mut falloff = vec![0.0; points.len()];
+                    falloff.extend(f);
                     points.extend(p);
                     triangles.extend(t);
-                    self.mesh = Some(DensityMesh { points, triangles });
-                    Ok(ProcessStatus::MeshChanged)
+                    let mesh = self.make_mesh(points, triangles, falloff, &settings);
+                    Ok(self.begin_stage(mesh, settings, 0))
                 }
-            }
-        } else if let Some((points, settings)) = self.queue.pop_front() {
-            let scale = self.map.scale();
-            let remaining = self
-                .map
-                .value_steepness_iter()
-                .filter_map(|(x, y, v, s)| {
-                    if v > settings.visibility_threshold && s > settings.steepness_threshold {
-                        let x = (x * scale) as Scalar;
-                        let y = (y * scale) as Scalar;
-                        let lpss = match settings.points_separation {
-                            PointsSeparation::Constant(v) => v * v,
-                            PointsSeparation::SteepnessMapping(f, t) => {
-                                let v = Self::lerp(s, t, f);
-                                v * v
+                ProcessingChange::Stage {
+                    settings,
+                    mut mesh,
+                    stage,
+                    step,
+                    steps,
+                } => match self.stages.0.get(stage) {
+                    Some(processing_stage) if step < steps => {
+                        if let Err(error) =
+                            processing_stage.step(step, &mut mesh, &self.map, &settings)
+                        {
+                            // failed change is processed once more, in case the failure was
+                            // temporary, and dropped when it fails again.
+                            self.pending_cache = None;
+                            if let Some((change, false)) = self.processed.take() {
+                                self.queue.push_front(change);
+                                self.retrying = true;
                             }
-                        };
-                        Some((Coord::new(x, y), v, s, lpss))
-                    } else {
-                        None
+                            return Err(error);
+                        }
+                        self.current = Some(ProcessingChange::Stage {
+                            settings,
+                            mesh,
+                            stage,
+                            step: step + 1,
+                            steps,
+                        });
+                        Ok(ProcessStatus::InProgress)
                     }
-                })
-                .collect::<Vec<_>>();
-            let progress_limit = remaining.len();
-            let tries = settings.max_iterations;
-            self.current = Some(ProcessingChange::FindingPoints {
+                    _ => Ok(self.begin_stage(mesh, settings, stage + 1)),
+                },
+            }
+        } else if let Some((points, region, settings)) = self.queue.pop_front() {
+            let retry = std::mem::take(&mut self.retrying);
+            self.processed = Some(((points.clone(), region, settings.clone()), retry));
+            self.pending_cache = None;
+            self.current = region
+                .and_then(|region| self.begin_region_change(region, &settings))
+                .or_else(|| Some(self.begin_change(points, settings)));
+            Ok(ProcessStatus::InProgress)
+        } else {
+            Ok(ProcessStatus::Idle)
+        }
+    }
+
+    fn begin_change(
+        &self,
+        points: Vec<Coord>,
+        settings: GenerateDensityMeshSettings,
+    ) -> ProcessingChange {
+        if let PointsPlacement::ErrorDriven {
+            error_tolerance,
+            max_points,
+        } = settings.points_placement
+        {
+            let progress_limit = max_points
+                .unwrap_or(usize::MAX)
+                .min(self.map.values().len());
+            let insertion =
+                ErrorDrivenInsertion::new(&self.map, &points, error_tolerance, progress_limit);
+            return ProcessingChange::InsertingPoints {
+                settings,
+                insertion,
+                progress_limit,
+            };
+        }
+        if let PointsPlacement::TightPolygon(_) = settings.points_placement {
+            return ProcessingChange::Triangulate {
+                settings,
+                points: vec![],
+                region: None,
+                progress_limit: 1,
+            };
+        }
+        if settings.points_placement == PointsPlacement::PoissonDisc {
+            return self.begin_sampling(points, settings);
+        }
+        let scale = self.map.scale().max(1);
+        let width = self.map.unscaled_width();
+        let values = self.map.values();
+        let steepness = self.map.steepness();
+        let scoring = self.strategies.point_scoring();
+        let mut remaining = into_iter!((0..values.len()))
+            .filter_map(|i| {
+                Self::candidate(
+                    i % width,
+                    i / width,
+                    values[i],
+                    steepness[i],
+                    scale,
+                    &settings,
+                    scoring,
+                )
+            })
+            .collect::<Vec<_>>();
+        sort_by!(remaining, |a, b| a.2.total_cmp(&b.2));
+        let mut grid = PointsGrid::new(
+            Coord::default(),
+            Coord::new(self.map.width() as _, self.map.height() as _),
+            settings.points_separation.maximum(),
+        );
+        for (i, p) in points.iter().enumerate() {
+            grid.insert(i, *p);
+        }
+        let progress_limit = remaining.len();
+        ProcessingChange::FindingPoints {
+            tries: settings.max_iterations,
+            settings,
+            remaining,
+            points,
+            grid,
+            region: None,
+            progress_current: 0,
+            progress_limit,
+        }
+    }
+
+    fn begin_sampling(
+        &self,
+        points: Vec<Coord>,
+        settings: GenerateDensityMeshSettings,
+    ) -> ProcessingChange {
+        let scale = self.map.scale().max(1);
+        let width = self.map.unscaled_width();
+        let mut remaining = self
+            .map
+            .values()
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| **v > settings.visibility_threshold)
+            .map(|(i, _)| Coord::new(((i % width) * scale) as _, ((i / width) * scale) as _))
+            .collect::<Vec<_>>();
+        let mut random = Random::new(settings.seed);
+        random.shuffle(&mut remaining);
+        let mut grid = PointsGrid::new(
+            Coord::default(),
+            Coord::new(self.map.width() as _, self.map.height() as _),
+            settings.points_separation.maximum(),
+        );
+        for (i, p) in points.iter().enumerate() {
+            grid.insert(i, *p);
+        }
+        let active = (0..points.len()).collect();
+        let progress_limit = remaining.len();
+        ProcessingChange::SamplingPoints {
+            settings,
+            random,
+            remaining,
+            active,
+            points,
+            grid,
+            progress_current: 0,
+            progress_limit,
+        }
+    }
+
+    /// Try to find free space around random active point, which gets deactivated on failure.
+    fn sample_around(
+        &self,
+        random: &mut Random,
+        active: &mut Vec<usize>,
+        points: &[Coord],
+        grid: &PointsGrid,
+        settings: &GenerateDensityMeshSettings,
+    ) -> Option<Coord> {
+        let index = random.next_index(active.len());
+        let origin = points[active[index]];
+        let radius = Self::separation(self.map.sample_steepness(origin), settings).sqrt();
+        // annulus sampling below would never finish for degenerate radius.
+        if !radius.is_finite() || radius <= 0.0 {
+            active.swap_remove(index);
+            return None;
+        }
+        let max = Coord::new(
+            ((self.map.unscaled_width() - 1) * self.map.scale().max(1)) as _,
+            ((self.map.unscaled_height() - 1) * self.map.scale().max(1)) as _,
+        );
+        for _ in 0..settings.max_step_rejections.max(1) {
+            // rejection sampling of annulus avoids trigonometry, which results might differ
+            // between platforms.
+            let offset = loop {
+                let offset = Coord::new(
+                    (random.next_scalar() * 4.0 - 2.0) * radius,
+                    (random.next_scalar() * 4.0 - 2.0) * radius,
+                );
+                let sqr_distance = offset.sqr_magnitude();
+                if sqr_distance >= radius * radius && sqr_distance <= 4.0 * radius * radius {
+                    break offset;
+                }
+            };
+            let point = origin + offset;
+            if point.x < 0.0 || point.y < 0.0 || point.x > max.x || point.y > max.y {
+                continue;
+            }
+            if self.map.sample_value(point) <= settings.visibility_threshold {
+                continue;
+            }
+            let lpss = Self::separation(self.map.sample_steepness(point), settings);
+            if !grid.has_any_within(point, lpss, points) {
+                return Some(point);
+            }
+        }
+        active.swap_remove(index);
+        None
+    }
+
+    fn begin_region_change(
+        &self,
+        (col, row, width, height): MapRegion,
+        settings: &GenerateDensityMeshSettings,
+    ) -> Option<ProcessingChange> {
+        let cache = self.cache.as_ref()?;
+        // only steepness placement is local, others depend on the whole map so are rebuilt.
+        if cache.settings != *settings
+            || settings.points_placement != PointsPlacement::Steepness
+            || settings.constrain_contour
+            || Self::is_refined(settings)
+        {
+            return None;
+        }
+        let scale = self.map.scale().max(1);
+        let map_width = self.map.unscaled_width();
+        let map_height = self.map.unscaled_height();
+        // steepness of pixels around changed region changes too.
+        let radius = self.map.steepness_kernel().radius();
+        let fc = col.saturating_sub(radius);
+        let fr = row.saturating_sub(radius);
+        let tc = (col + width + radius).min(map_width);
+        let tr = (row + height + radius).min(map_height);
+        let margin = settings.update_region_margin.max(0.0);
+        let min = Coord::new(
+            (fc * scale) as Scalar - margin,
+            (fr * scale) as Scalar - margin,
+        );
+        let max = Coord::new(
+            (tc * scale) as Scalar + margin,
+            (tr * scale) as Scalar + margin,
+        );
+        let is_inside = |p: Coord| p.x >= min.x && p.x < max.x && p.y >= min.y && p.y < max.y;
+
+        let mut remap = vec![None; cache.points.len()];
+        let mut points = Vec::with_capacity(cache.points.len());
+        for (i, p) in cache.points.iter().enumerate() {
+            if !is_inside(*p) {
+                remap[i] = Some(points.len());
+                points.push(*p);
+            }
+        }
+        let kept_points = points.len();
+
+        let mut triangles = Vec::with_capacity(cache.triangles.len());
+        let mut visibility = Vec::with_capacity(cache.triangles.len());
+        let mut removed = vec![];
+        for (t, v) in cache.triangles.iter().zip(cache.visibility.iter()) {
+            let a = cache.points[t.a];
+            let b = cache.points[t.b];
+            let c = cache.points[t.c];
+            let overlaps = a.x.max(b.x).max(c.x) >= min.x
+                && a.x.min(b.x).min(c.x) <= max.x
+                && a.y.max(b.y).max(c.y) >= min.y
+                && a.y.min(b.y).min(c.y) <= max.y;
+            match (overlaps, remap[t.a], remap[t.b], remap[t.c]) {
+                (false, Some(a), Some(b), Some(c)) => {
+                    triangles.push(Triangle { a, b, c });
+                    visibility.push(*v);
+                }
+                _ => removed.push(*t),
+            }
+        }
+
+        let mut edges = HashMap::<(usize, usize), usize>::with_capacity(removed.len() * 3);
+        for t in &removed {
+            for (from, to) in [(t.a, t.b), (t.b, t.c), (t.c, t.a)].iter() {
+                *edges.entry(Self::edge_key(*from, *to)).or_default() += 1;
+            }
+        }
+        // removed triangles edges that are not shared between them.
+        let mut outline = HashMap::with_capacity(edges.len());
+        let mut enclosed = !removed.is_empty();
+        for ((from, to), count) in edges {
+            if count == 1 {
+                if let (Some(from), Some(to)) = (remap[from], remap[to]) {
+                    outline.insert(Self::edge_key(from, to), false);
+                } else {
+                    enclosed = false;
+                }
+            }
+        }
+        for t in &triangles {
+            for (from, to) in [(t.a, t.b), (t.b, t.c), (t.c, t.a)].iter() {
+                if let Some(shared) = outline.get_mut(&Self::edge_key(*from, *to)) {
+                    *shared = true;
+                }
+            }
+        }
+        let enclosed = enclosed && outline.values().all(|shared| *shared);
+        let boundary = outline.into_keys().collect();
+        let mut ring = removed
+            .iter()
+            .flat_map(|t| vec![remap[t.a], remap[t.b], remap[t.c]])
+            .flatten()
+            .collect::<Vec<_>>();
+        ring.sort_unstable();
+        ring.dedup();
+        let cavity = removed
+            .iter()
+            .map(|t| [cache.points[t.a], cache.points[t.b], cache.points[t.c]])
+            .collect();
+
+        let fc = ((min.x / scale as Scalar).ceil().max(0.0) as usize).min(map_width);
+        let fr = ((min.y / scale as Scalar).ceil().max(0.0) as usize).min(map_height);
+        let tc = ((max.x / scale as Scalar).ceil().max(0.0) as usize).min(map_width);
+        let tr = ((max.y / scale as Scalar).ceil().max(0.0) as usize).min(map_height);
+        let values = self.map.values();
+        let steepness = self.map.steepness();
+        let scoring = self.strategies.point_scoring();
+        let mut remaining = (fr..tr)
+            .flat_map(|y| (fc..tc).map(move |x| (x, y)))
+            .filter_map(|(x, y)| {
+                let i = y * map_width + x;
+                Self::candidate(x, y, values[i], steepness[i], scale, settings, scoring)
+            })
+            .filter(|(p, _, _, _)| is_inside(*p))
+            .collect::<Vec<_>>();
+        sort_by!(remaining, |a, b| a.2.total_cmp(&b.2));
+        // only kept points that are close enough to the region can reject its candidates.
+        let separation = settings.points_separation.maximum();
+        let mut grid = PointsGrid::new(min - separation, max + separation, separation);
+        for (i, p) in points.iter().enumerate() {
+            if p.x >= min.x - separation
+                && p.x < max.x + separation
+                && p.y >= min.y - separation
+                && p.y < max.y + separation
+            {
+                grid.insert(i, *p);
+            }
+        }
+        let progress_limit = remaining.len();
+        Some(ProcessingChange::FindingPoints {
+            settings: settings.clone(),
+            tries: settings.max_iterations,
+            remaining,
+            points,
+            grid,
+            region: Some(RegionChange {
+                kept_points,
+                triangles,
+                visibility,
+                cavity,
+                boundary,
+                ring,
+                enclosed,
+            }),
+            progress_current: 0,
+            progress_limit,
+        })
+    }
+
+    fn triangulated(
+        &mut self,
+        points: Vec<Coord>,
+        triangles: Vec<Triangle>,
+        visibility: Vec<Option<bool>>,
+        settings: GenerateDensityMeshSettings,
+        progress_limit: usize,
+    ) -> ProcessStatus {
+        if !settings.keep_invisible_triangles {
+            self.current = Some(ProcessingChange::RemoveInvisibleTriangles {
                 settings,
-                tries,
-                remaining,
                 points,
-                progress_current: 0,
+                triangles,
+                visibility,
                 progress_limit,
             });
-            Ok(ProcessStatus::InProgress)
+            ProcessStatus::InProgress
         } else {
-            Ok(ProcessStatus::Idle)
+            // triangles known to be invisible are outside of constrained outline.
+            let kept = triangles
+                .iter()
+                .zip(visibility.iter())
+                .filter(|(_, v)| **v != Some(false))
+                .map(|(t, _)| *t)
+                .collect::<Vec<_>>();
+            self.pending_cache = Some(TriangulationCache {
+                settings: settings.clone(),
+                points: points.clone(),
+                triangles,
+                visibility,
+            });
+            self.complete(points, kept, &settings, progress_limit)
+        }
+    }
+
+    fn complete(
+        &mut self,
+        points: Vec<Coord>,
+        triangles: Vec<Triangle>,
+        settings: &GenerateDensityMeshSettings,
+        progress_limit: usize,
+    ) -> ProcessStatus {
+        if let Some(size) = settings.extrude_size {
+            self.current = Some(ProcessingChange::Extrude {
+                points,
+                triangles,
+                size,
+                settings: settings.clone(),
+                progress_limit,
+            });
+            ProcessStatus::InProgress
+        } else {
+            let mesh = self.make_mesh(points, triangles, vec![], settings);
+            self.begin_stage(mesh, settings.clone(), 0)
+        }
+    }
+
+    /// Run processing stage on built mesh or publish the mesh when there are no more stages.
+    fn begin_stage(
+        &mut self,
+        mut mesh: DensityMesh,
+        settings: GenerateDensityMeshSettings,
+        stage: usize,
+    ) -> ProcessStatus {
+        match self.stages.0.get(stage) {
+            Some(processing_stage) => {
+                let steps = processing_stage.steps(&mesh, &self.map, &settings);
+                self.current = Some(ProcessingChange::Stage {
+                    settings,
+                    mesh,
+                    stage,
+                    step: 0,
+                    steps,
+                });
+                ProcessStatus::InProgress
+            }
+            None => {
+                // stages might have moved points, so attributes follow them.
+                if stage > 0 {
+                    self.update_attributes(&mut mesh, &settings);
+                }
+                self.mesh = Some(mesh);
+                self.processed = None;
+                if let Some(cache) = self.pending_cache.take() {
+                    self.cache = Some(cache);
+                }
+                ProcessStatus::MeshChanged
+            }
+        }
+    }
+
+    /// [(coordinate, value, score, local point separation squared)]
+    #[inline]
+    fn candidate(
+        x: usize,
+        y: usize,
+        v: Scalar,
+        s: Scalar,
+        scale: usize,
+        settings: &GenerateDensityMeshSettings,
+        scoring: &dyn PointScoring,
+    ) -> Option<(Coord, Scalar, Scalar, Scalar)> {
+        if v > settings.visibility_threshold && s > settings.steepness_threshold {
+            let point = Coord::new((x * scale) as Scalar, (y * scale) as Scalar);
+            let lpss = Self::separation(s, settings);
+            let score = scoring.score(point, v, s);
+            // candidates without meaningful score are placed last.
+            let score = if score.is_nan() {
+                Scalar::NEG_INFINITY
+            } else {
+                score
+            };
+            Some((point, v, score, lpss))
+        } else {
+            None
         }
     }
 
+    /// Local point separation squared.
+    #[inline]
+    fn separation(steepness: Scalar, settings: &GenerateDensityMeshSettings) -> Scalar {
+        let v = settings.points_separation.local(steepness);
+        v * v
+    }
+
     /// Process incoming changes until none is left to do.
     ///
     /// # Returns
@@ -366,27 +1245,28 @@ impl DensityMeshGenerator {
     /// Process incoming changes until none is left to do.
     ///
     /// # Arguments
-    /// * `f` - Callback triggered on every processing step. Signature: `fn(progress, limit, factor)`.
+    /// * `f` - Callback triggered on every processing step with current progress report.
     ///
     /// # Returns
     /// Ok or generation error.
     pub fn process_wait_tracked<F>(&mut self, mut f: F) -> Result<(), GenerateDensityMeshError>
     where
-        F: FnMut(usize, usize, Scalar),
+        F: FnMut(ProgressReport),
     {
-        let (c, l, p) = self.progress();
-        f(c, l, p);
-        while self.process()? == ProcessStatus::InProgress {
-            let (c, l, p) = self.progress();
-            f(c, l, p);
+        f(self.progress());
+        loop {
+            let status = self.process()?;
+            f(self.progress());
+            if status != ProcessStatus::InProgress {
+                return Ok(());
+            }
         }
-        Ok(())
     }
 
     /// Process incoming changes until none is left to do.
     ///
     /// # Arguments
-    /// * `f` - Callback triggered on every processing step. Signature: `fn(progress, limit, factor)`.
+    /// * `f` - Callback triggered on every processing step with current progress report.
     /// * `timeout` - Duration of time that processing can take.
     ///
     /// # Returns
@@ -397,111 +1277,537 @@ impl DensityMeshGenerator {
         timeout: Duration,
     ) -> Result<ProcessStatus, GenerateDensityMeshError>
     where
-        F: FnMut(usize, usize, Scalar),
+        F: FnMut(ProgressReport),
     {
         let timer = Instant::now();
-        let (c, l, p) = self.progress();
-        f(c, l, p);
+        f(self.progress());
         loop {
             let status = self.process()?;
-            let (c, l, p) = self.progress();
-            f(c, l, p);
+            f(self.progress());
             if status != ProcessStatus::InProgress || timer.elapsed() > timeout {
                 return Ok(status);
             }
         }
     }
 
+    /// Extrude mesh outline by given size.
+    ///
+    /// # Returns
+    /// Extruded points, triangles and points falloff, or error when mesh outline can not be
+    /// found.
     fn extrude(
         points: &[Coord],
         triangles: &[Triangle],
         size: Scalar,
-    ) -> (Vec<Coord>, Vec<Triangle>) {
-        let edges = triangles
+        settings: &ExtrudeSettings,
+    ) -> Result<ExtrudedOutline, GenerateDensityMeshError> {
+        let half_edges = HalfEdgeMesh::new(points, triangles)?;
+        let loops = half_edges.boundary_loops()?;
+        // outline edges of all loops, each followed by the next one of its loop.
+        let mut outline = Vec::with_capacity(loops.iter().map(|l| l.len()).sum());
+        let mut next = Vec::with_capacity(outline.capacity());
+        for boundary_loop in &loops {
+            let first = outline.len();
+            for (i, half_edge) in boundary_loop.iter().enumerate() {
+                outline.push(half_edges.half_edge(*half_edge));
+                next.push(first + (i + 1) % boundary_loop.len());
+            }
+        }
+        // corner where outline edge starts gets fan of offset directions for unit size.
+        let mut fans = vec![vec![]; outline.len()];
+        for ((from, to), n) in outline.iter().zip(next.iter()) {
+            fans[*n] =
+                Self::extrude_corner(points[*from], points[*to], points[outline[*n].1], settings);
+        }
+        let rings = settings.rings.max(1);
+        let mut starts = Vec::with_capacity(fans.len());
+        let mut fans_size = 0;
+        for fan in &fans {
+            starts.push(fans_size);
+            fans_size += fan.len();
+        }
+        let index = |corner: usize, f: usize, ring: usize| {
+            if ring == 0 {
+                outline[corner].0
+            } else {
+                points.len() + (ring - 1) * fans_size + starts[corner] + f
+            }
+        };
+        let mut offsets = Vec::with_capacity(fans_size * rings);
+        let mut falloff = Vec::with_capacity(fans_size * rings);
+        for ring in 1..=rings {
+            let factor = ring as Scalar / rings as Scalar;
+            for (corner, fan) in fans.iter().enumerate() {
+                let origin = points[outline[corner].0];
+                offsets.extend(fan.iter().map(|v| origin + *v * (size * factor)));
+                falloff.extend(fan.iter().map(|_| factor));
+            }
+        }
+        // pairs of offset directions, along corner fans and along outline edges.
+        let segments = fans
             .iter()
             .enumerate()
-            .flat_map(|(i, t)| vec![(i, t.a, t.b), (i, t.b, t.c), (i, t.c, t.a)])
+            .flat_map(|(corner, fan)| (1..fan.len()).map(move |f| ((corner, f - 1), (corner, f))))
+            .chain(
+                next.iter()
+                    .enumerate()
+                    .map(|(i, n)| ((i, fans[i].len() - 1), (*n, 0))),
+            )
             .collect::<Vec<_>>();
-        let outline = edges
-            .iter()
-            .filter(|e1| {
-                !edges
-                    .iter()
-                    .any(|e2| e1.0 != e2.0 && Self::are_edges_connected(e1.1, e1.2, e2.1, e2.2))
-            })
+        let mut extruded = Vec::with_capacity(segments.len() * rings * 2);
+        for ring in 1..=rings {
+            for ((cu, fu), (cv, fv)) in &segments {
+                let (pu, pv) = (index(*cu, *fu, ring - 1), index(*cv, *fv, ring - 1));
+                let (qu, qv) = (index(*cu, *fu, ring), index(*cv, *fv, ring));
+                if pu != pv {
+                    extruded.push([pv, pu, qu]);
+                }
+                extruded.push([qu, qv, pv]);
+            }
+        }
+        // rings placed inside of mesh have reversed winding.
+        let inward = settings.direction == ExtrudeDirection::Inward;
+        let extruded = extruded
+            .into_iter()
+            .map(|[a, b, c]| if inward { [a, c, b] } else { [a, b, c] }.into())
             .collect::<Vec<_>>();
-        let offsets = outline
+        Ok((offsets, extruded, falloff))
+    }
+
+    /// Create mesh with attribute channels enabled in settings.
+    fn make_mesh(
+        &self,
+        points: Vec<Coord>,
+        triangles: Vec<Triangle>,
+        falloff: Vec<Scalar>,
+        settings: &GenerateDensityMeshSettings,
+    ) -> DensityMesh {
+        let mut mesh = DensityMesh {
+            points,
+            triangles,
+            falloff,
+            ..Default::default()
+        };
+        self.update_attributes(&mut mesh, settings);
+        mesh
+    }
+
+    /// Fill attribute channels enabled in settings from current mesh points.
+    fn update_attributes(&self, mesh: &mut DensityMesh, settings: &GenerateDensityMeshSettings) {
+        let attributes = &settings.attributes;
+        let values = if attributes.values {
+            mesh.points
+                .iter()
+                .map(|p| self.map.sample_value(*p))
+                .collect()
+        } else {
+            vec![]
+        };
+        let steepness = if attributes.steepness {
+            mesh.points
+                .iter()
+                .map(|p| self.map.sample_steepness(*p))
+                .collect()
+        } else {
+            vec![]
+        };
+        let uvs = if attributes.uvs {
+            let size = Coord::new(
+                self.map.width().max(1) as Scalar,
+                self.map.height().max(1) as Scalar,
+            );
+            mesh.points
+                .iter()
+                .map(|p| Coord::new(p.x / size.x, p.y / size.y))
+                .collect()
+        } else {
+            vec![]
+        };
+        let outline = if attributes.outline {
+            match mesh.half_edges() {
+                Ok(half_edges) => (0..mesh.points.len())
+                    .map(|i| half_edges.is_boundary_vertex(i))
+                    .collect(),
+                Err(_) => vec![false; mesh.points.len()],
+            }
+        } else {
+            vec![]
+        };
+        mesh.values = values;
+        mesh.steepness = steepness;
+        mesh.uvs = uvs;
+        mesh.outline = outline;
+    }
+
+    /// Offset directions (for unit extrude size) of outline corner.
+    ///
+    /// # Arguments
+    /// * `prev` - Previous outline point.
+    /// * `point` - Corner point.
+    /// * `next` - Next outline point.
+    /// * `settings` - Extrude settings.
+    fn extrude_corner(
+        prev: Coord,
+        point: Coord,
+        next: Coord,
+        settings: &ExtrudeSettings,
+    ) -> Vec<Coord> {
+        let outward = settings.direction == ExtrudeDirection::Outward;
+        let normal = |direction: Coord| {
+            if outward {
+                -direction.right()
+            } else {
+                direction.right()
+            }
+        };
+        let da = (point - prev).normalized();
+        let db = (next - point).normalized();
+        let (na, nb) = (normal(da), normal(db));
+        let cross = da.x * db.y - da.y * db.x;
+        let spike = cross == 0.0 && da.dot(db) < 0.0;
+        // convex corners leave gap between offset edges.
+        let convex = spike || if outward { cross < 0.0 } else { cross > 0.0 };
+        let bisector = na + nb;
+        let miter = if bisector.sqr_magnitude() > 1.0e-12 {
+            let bisector = bisector.normalized();
+            Some(bisector / bisector.dot(na).max(1.0e-6))
+        } else {
+            None
+        };
+        let miter_limit = settings.miter_limit.max(1.0);
+        if !convex {
+            let miter = miter.unwrap_or(na);
+            let length = miter.magnitude();
+            return if length > miter_limit {
+                vec![miter * (miter_limit / length)]
+            } else {
+                vec![miter]
+            };
+        }
+        match settings.join {
+            ExtrudeJoin::Miter => match miter {
+                Some(miter) if miter.magnitude() <= miter_limit => vec![miter],
+                _ => vec![na, nb],
+            },
+            ExtrudeJoin::Bevel => vec![na, nb],
+            ExtrudeJoin::Round => {
+                let sweep = if spike {
+                    if outward {
+                        -std::f32::consts::PI
+                    } else {
+                        std::f32::consts::PI
+                    }
+                } else {
+                    (na.x * nb.y - na.y * nb.x).atan2(na.dot(nb))
+                };
+                let steps = (sweep.abs() / ROUND_JOIN_STEP).ceil().max(1.0) as usize;
+                (0..=steps)
+                    .map(|i| {
+                        let (sin, cos) = (sweep * i as Scalar / steps as Scalar).sin_cos();
+                        Coord::new(na.x * cos - na.y * sin, na.x * sin + na.y * cos)
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    fn triangulate(points: &[Coord]) -> Result<Vec<Triangle>, GenerateDensityMeshError> {
+        let dpoints = points
             .iter()
-            .map(|(_, m, n)| {
-                let i = *m;
-                let p = outline.iter().find(|(_, _, p)| p == m).unwrap().1;
-                let p = points[p];
-                let m = points[*m];
-                let n = points[*n];
-                let pm = -(m - p).normalized().right();
-                let mn = -(n - m).normalized().right();
-                (i, m + (pm + mn).normalized() * size)
+            .map(|v| Point::new(v.x, v.y))
+            .collect::<Vec<_>>();
+        let triangulation = if let Some(triangulation) = Delaunay::new(&dpoints) {
+            triangulation
+        } else {
+            return Err(GenerateDensityMeshError::FailedTriangulation);
+        };
+        Ok(triangulation
+            .dcel
+            .vertices
+            .chunks(3)
+            .map(|t| Triangle {
+                a: t[0],
+                b: t[1],
+                c: t[2],
             })
+            .collect::<Vec<_>>())
+    }
+
+    /// Returns stages that change with given settings goes through.
+    fn planned_stages(&self, settings: &GenerateDensityMeshSettings) -> Vec<ProgressStage> {
+        let mut result = vec![];
+        match settings.points_placement {
+            PointsPlacement::Steepness => result.push(ProgressStage::FindingPoints),
+            PointsPlacement::PoissonDisc => result.push(ProgressStage::SamplingPoints),
+            PointsPlacement::ErrorDriven { .. } => result.push(ProgressStage::InsertingPoints),
+            PointsPlacement::TightPolygon(_) => {}
+        }
+        result.push(ProgressStage::Triangulating);
+        if Self::is_refined(settings) {
+            result.push(ProgressStage::Refining);
+        }
+        if !settings.keep_invisible_triangles {
+            result.push(ProgressStage::RemovingInvisibleTriangles);
+        }
+        if settings.extrude_size.is_some() {
+            result.push(ProgressStage::Extruding);
+        }
+        result.extend((0..self.stages.0.len()).map(ProgressStage::Custom));
+        result
+    }
+
+    fn is_refined(settings: &GenerateDensityMeshSettings) -> bool {
+        settings.min_angle.is_some() || settings.max_triangle_area.is_some()
+    }
+
+    fn is_constrained(settings: &GenerateDensityMeshSettings) -> bool {
+        settings.constrain_contour
+            || matches!(settings.points_placement, PointsPlacement::TightPolygon(_))
+    }
+
+    /// Continue with triangulation made by internal Delaunay triangulation.
+    fn delaunay_triangulated(
+        &mut self,
+        triangulation: DelaunayTriangulation,
+        settings: GenerateDensityMeshSettings,
+        progress_limit: usize,
+    ) -> ProcessStatus {
+        let inside = if Self::is_constrained(&settings) {
+            Some(
+                triangulation
+                    .inside_triangles(|p| self.map.sample_value(p) > settings.visibility_threshold),
+            )
+        } else {
+            None
+        };
+        if Self::is_refined(&settings) {
+            let max_points = settings
+                .max_steiner_points
+                .unwrap_or_else(|| self.map.values().len());
+            let refinement = Refinement::new(triangulation, inside, &settings, max_points);
+            self.current = Some(ProcessingChange::Refining {
+                settings,
+                refinement,
+                progress_limit,
+            });
+            ProcessStatus::InProgress
+        } else {
+            self.finish_triangulation(triangulation, inside, settings, progress_limit)
+        }
+    }
+
+    fn finish_triangulation(
+        &mut self,
+        triangulation: DelaunayTriangulation,
+        inside: Option<Vec<bool>>,
+        settings: GenerateDensityMeshSettings,
+        progress_limit: usize,
+    ) -> ProcessStatus {
+        // triangles outside of the outline are invisible no matter of their coverage, while
+        // inside ones are visible unless custom triangle filter decides otherwise.
+        let filtered = self.strategies.triangle_filter.is_some();
+        let visibility = match inside {
+            Some(inside) => inside
+                .into_iter()
+                .map(|inside| match (inside, filtered) {
+                    (false, _) => Some(false),
+                    (true, false) => Some(true),
+                    (true, true) => None,
+                })
+                .collect(),
+            None => vec![None; triangulation.triangles().len()],
+        };
+        let (points, triangles) = triangulation.into_mesh();
+        self.triangulated(points, triangles, visibility, settings, progress_limit)
+    }
+
+    /// Triangulate points, optionally together with the outline of visible area.
+    fn triangulate_delaunay(
+        &self,
+        points: &[Coord],
+        settings: &GenerateDensityMeshSettings,
+    ) -> Result<DelaunayTriangulation, GenerateDensityMeshError> {
+        let mut triangulation = DelaunayTriangulation::from_map(&self.map);
+        if let PointsPlacement::TightPolygon(simplification) = &settings.points_placement {
+            let (min, max) = triangulation.bounds();
+            let contours = extract_contours(&self.map, settings.visibility_threshold, true)
+                .iter()
+                // inflated outline can not go outside of triangulation bounds.
+                .map(|contour| contour.simplified(simplification).clipped(min, max))
+                .filter(|contour| contour.points.len() >= 3)
+                .collect::<Vec<_>>();
+            Self::constrain_contour(&mut triangulation, &contours)?;
+            return Ok(triangulation);
+        }
+        if !settings.constrain_contour {
+            for p in points {
+                triangulation.insert(*p);
+            }
+            return Ok(triangulation);
+        }
+        let contours = extract_contours(&self.map, settings.visibility_threshold, true);
+        let vertices = contours
+            .iter()
+            .flat_map(|contour| contour.points.iter())
+            .copied()
             .collect::<Vec<_>>();
-        let triangles = outline
-            .into_iter()
-            .flat_map(|(_, a, b)| {
-                let ea = offsets.iter().position(|(ea, _)| ea == a).unwrap() + points.len();
-                let eb = offsets.iter().position(|(eb, _)| eb == b).unwrap() + points.len();
-                vec![[*b, *a, ea].into(), [ea, eb, *b].into()]
+        let spacing = self.map.scale().max(1) as Scalar * 0.5;
+        let mut grid = PointsGrid::new(
+            Coord::default(),
+            Coord::new(self.map.width() as _, self.map.height() as _),
+            spacing,
+        );
+        for (i, p) in vertices.iter().enumerate() {
+            grid.insert(i, *p);
+        }
+        // points placed too close to the outline would produce slivers along it.
+        for p in points {
+            if !grid.has_any_within(*p, spacing * spacing, &vertices) {
+                triangulation.insert(*p);
+            }
+        }
+        Self::constrain_contour(&mut triangulation, &contours)?;
+        Ok(triangulation)
+    }
+
+    /// Insert outline loops into triangulation as constrained edges.
+    ///
+    /// # Returns
+    /// Ok or error when any outline edge could not be inserted, since triangles inside of the
+    /// outline could not be told apart from the ones outside of it.
+    fn constrain_contour(
+        triangulation: &mut DelaunayTriangulation,
+        contours: &[Contour],
+    ) -> Result<(), GenerateDensityMeshError> {
+        let loops = contours
+            .iter()
+            .map(|contour| {
+                contour
+                    .points
+                    .iter()
+                    .filter_map(|p| triangulation.insert(*p).map(|(i, _)| i))
+                    .collect::<Vec<_>>()
             })
             .collect::<Vec<_>>();
-        (
-            offsets.into_iter().map(|(_, p)| p).collect::<Vec<_>>(),
-            triangles,
-        )
+        for indices in loops {
+            for (i, from) in indices.iter().enumerate() {
+                triangulation.insert_segment(*from, indices[(i + 1) % indices.len()])?;
+            }
+        }
+        Ok(())
     }
 
-    fn are_edges_connected(a_from: usize, a_to: usize, b_from: usize, b_to: usize) -> bool {
-        (a_from == b_from && a_to == b_to) || (a_from == b_to && a_to == b_from)
+    fn triangulate_region(
+        points: &[Coord],
+        region: RegionChange,
+    ) -> Result<(Vec<Triangle>, Vec<Option<bool>>), GenerateDensityMeshError> {
+        if region.enclosed {
+            if let Some(triangles) = Self::triangulate_cavity(points, &region) {
+                let RegionChange {
+                    triangles: mut result,
+                    mut visibility,
+                    ..
+                } = region;
+                visibility.resize(result.len() + triangles.len(), None);
+                result.extend(triangles);
+                return Ok((result, visibility));
+            }
+        }
+        // cavity cannot be filled locally so triangulate everything, but still reuse known
+        // visibility of triangles that did not change.
+        let triangles = Self::triangulate(points)?;
+        let known = region
+            .triangles
+            .iter()
+            .zip(region.visibility)
+            .map(|(t, v)| (Self::triangle_key(t), v))
+            .collect::<HashMap<_, _>>();
+        let visibility = triangles
+            .iter()
+            .map(|t| known.get(&Self::triangle_key(t)).copied().flatten())
+            .collect::<Vec<_>>();
+        Ok((triangles, visibility))
     }
 
-    fn is_triangle_visible(
-        a: Coord,
-        b: Coord,
-        c: Coord,
-        map: &DensityMap,
-        settings: &GenerateDensityMeshSettings,
-    ) -> bool {
-        let fx = (a.x as isize).min(b.x as isize).min(c.x as isize);
-        let fy = (a.y as isize).min(b.y as isize).min(c.y as isize);
-        let tx = (a.x as isize).max(b.x as isize).max(c.x as isize);
-        let ty = (a.y as isize).max(b.y as isize).max(c.y as isize);
-        let nab = (b - a).right();
-        let nbc = (c - b).right();
-        let nca = (a - c).right();
-        let mut count = 0;
-        let mut samples = 0;
-        for y in fy..=ty {
-            for x in fx..=tx {
-                let p = Coord::new(x as _, y as _);
-                if (p - a).dot(nab) >= 0.0 && (p - b).dot(nbc) >= 0.0 && (p - c).dot(nca) >= 0.0 {
-                    samples += 1;
-                    if Self::is_point_visible((x, y), map, settings) {
-                        count += 1;
-                    }
-                }
+    fn triangulate_cavity(points: &[Coord], region: &RegionChange) -> Option<Vec<Triangle>> {
+        let indices = region
+            .ring
+            .iter()
+            .copied()
+            .chain(region.kept_points..points.len())
+            .collect::<Vec<_>>();
+        let local = indices.iter().map(|i| points[*i]).collect::<Vec<_>>();
+        let triangles = Self::triangulate(&local)
+            .ok()?
+            .into_iter()
+            .map(|t| Triangle {
+                a: indices[t.a],
+                b: indices[t.b],
+                c: indices[t.c],
+            })
+            .filter(|t| {
+                let p = (points[t.a] + points[t.b] + points[t.c]) / 3.0;
+                region
+                    .cavity
+                    .iter()
+                    .any(|[a, b, c]| Self::is_point_in_triangle(p, *a, *b, *c))
+            })
+            .collect::<Vec<_>>();
+        // new triangles have to exactly fill the cavity, otherwise they would overlap kept ones.
+        let mut edges = HashMap::<(usize, usize), usize>::with_capacity(triangles.len() * 3);
+        for t in &triangles {
+            for (from, to) in [(t.a, t.b), (t.b, t.c), (t.c, t.a)].iter() {
+                *edges.entry(Self::edge_key(*from, *to)).or_default() += 1;
             }
         }
-        count as Scalar / samples as Scalar > 0.5
+        let outline = edges
+            .into_iter()
+            .filter(|(_, count)| *count == 1)
+            .map(|(edge, _)| edge)
+            .collect::<HashSet<_>>();
+        let boundary = region.boundary.iter().copied().collect::<HashSet<_>>();
+        if outline != boundary {
+            return None;
+        }
+        let used = triangles
+            .iter()
+            .flat_map(|t| vec![t.a, t.b, t.c])
+            .filter(|i| *i >= region.kept_points)
+            .collect::<HashSet<_>>();
+        if used.len() != points.len() - region.kept_points {
+            return None;
+        }
+        Some(triangles)
     }
 
     #[inline]
-    fn is_point_visible(
-        pos: (isize, isize),
-        map: &DensityMap,
-        settings: &GenerateDensityMeshSettings,
-    ) -> bool {
-        map.value_at_point(pos) > settings.visibility_threshold
+    fn edge_key(from: usize, to: usize) -> (usize, usize) {
+        (from.min(to), from.max(to))
     }
 
     #[inline]
-    fn lerp(value: Scalar, from: Scalar, to: Scalar) -> Scalar {
-        from + (to - from) * value.max(0.0).min(1.0)
+    fn triangle_key(triangle: &Triangle) -> [usize; 3] {
+        let mut result = [triangle.a, triangle.b, triangle.c];
+        result.sort_unstable();
+        result
+    }
+
+    fn is_point_in_triangle(p: Coord, a: Coord, b: Coord, c: Coord) -> bool {
+        let ab = (b - a).right().dot(p - a);
+        let bc = (c - b).right().dot(p - b);
+        let ca = (a - c).right().dot(p - c);
+        (ab >= 0.0 && bc >= 0.0 && ca >= 0.0) || (ab <= 0.0 && bc <= 0.0 && ca <= 0.0)
+    }
+
+    /// Make sure visible pixels lookup matches current map, refreshing its changed rows.
+    fn refresh_visibility(&mut self, settings: &GenerateDensityMeshSettings) {
+        let threshold = settings.visibility_threshold;
+        // custom filters might need pixel values too.
+        let density = settings.triangle_visibility.needs_density()
+            || self.strategies.triangle_filter.is_some();
+        match &mut self.visibility {
+            Some(table) if table.matches(&self.map, threshold, density) => table.refresh(&self.map),
+            _ => self.visibility = Some(VisibilityTable::new(&self.map, threshold, density)),
+        }
     }
 }
@@ -0,0 +1,122 @@
+use crate::{coord::Coord, Scalar};
+use serde::{Deserialize, Serialize};
+
+/// Limit of cells per axis, so tiny points separation on big maps does not explode memory.
+const MAX_CELLS_PER_AXIS: usize = 1024;
+
+/// Uniform grid of points indices, used to quickly find points placed near given location.
+/// Points outside of the grid bounds are stored in the nearest border cell.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct PointsGrid {
+    origin: Coord,
+    cell_size: Scalar,
+    cols: usize,
+    rows: usize,
+    /// [[point index]]
+    cells: Vec<Vec<usize>>,
+}
+
+impl PointsGrid {
+    /// Create new empty grid.
+    ///
+    /// # Arguments
+    /// * `min` - Minimal grid bounds coordinate.
+    /// * `max` - Maximal grid bounds coordinate.
+    /// * `cell_size` - Preferred size of the grid cell.
+    pub fn new(min: Coord, max: Coord, cell_size: Scalar) -> Self {
+        let size = max - min;
+        let cell_size = cell_size
+            .max(size.x / MAX_CELLS_PER_AXIS as Scalar)
+            .max(size.y / MAX_CELLS_PER_AXIS as Scalar)
+            .max(1.0);
+        let cols = (size.x.max(0.0) / cell_size) as usize + 1;
+        let rows = (size.y.max(0.0) / cell_size) as usize + 1;
+        Self {
+            origin: min,
+            cell_size,
+            cols,
+            rows,
+            cells: vec![vec![]; cols * rows],
+        }
+    }
+
+    /// Store point index in the cell that contains given point.
+    pub fn insert(&mut self, index: usize, point: Coord) {
+        let col = self.col(point.x);
+        let row = self.row(point.y);
+        self.cells[row * self.cols + col].push(index);
+    }
+
+    /// Tells if any of stored points lay within given distance from the point.
+    ///
+    /// # Arguments
+    /// * `point` - Tested point.
+    /// * `sqr_distance` - Squared distance.
+    /// * `points` - Points referenced by stored indices.
+    pub fn has_any_within(&self, point: Coord, sqr_distance: Scalar, points: &[Coord]) -> bool {
+        let distance = sqr_distance.max(0.0).sqrt();
+        let fc = self.col(point.x - distance);
+        let tc = self.col(point.x + distance);
+        let fr = self.row(point.y - distance);
+        let tr = self.row(point.y + distance);
+        (fr..=tr).any(|row| {
+            self.cells[(row * self.cols + fc)..=(row * self.cols + tc)]
+                .iter()
+                .flatten()
+                .any(|i| (points[*i] - point).sqr_magnitude() <= sqr_distance)
+        })
+    }
+
+    #[inline]
+    fn col(&self, x: Scalar) -> usize {
+        (((x - self.origin.x) / self.cell_size).max(0.0) as usize).min(self.cols - 1)
+    }
+
+    #[inline]
+    fn row(&self, y: Scalar) -> usize {
+        (((y - self.origin.y) / self.cell_size).max(0.0) as usize).min(self.rows - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_points_grid_parity() {
+        // linear congruential generator keeps the point set stable between runs.
+        let mut seed = 0x2545_f491_u64;
+        let mut random = move || {
+            seed = seed
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (seed >> 33) as Scalar / (1u64 << 31) as Scalar
+        };
+        let (min, max) = (Coord::new(0.0, 0.0), Coord::new(100.0, 60.0));
+        // candidates also land past the grid bounds to exercise border cells.
+        let mut candidate = || Coord::new(random() * 140.0 - 20.0, random() * 100.0 - 20.0);
+        for &separation in &[0.5, 3.0, 7.5, 40.0] {
+            let sqr_distance = separation * separation;
+            let mut grid = PointsGrid::new(min, max, separation);
+            let mut points = vec![];
+            for _ in 0..2000 {
+                let point = candidate();
+                let brute = points
+                    .iter()
+                    .any(|p: &Coord| (*p - point).sqr_magnitude() <= sqr_distance);
+                assert_eq!(
+                    grid.has_any_within(point, sqr_distance, &points),
+                    brute,
+                    "separation: {}, point: {:?}",
+                    separation,
+                    point
+                );
+                if !brute {
+                    grid.insert(points.len(), point);
+                    points.push(point);
+                }
+            }
+            assert!(points.len() > 1);
+        }
+    }
+}
@@ -1,5 +1,12 @@
 use crate::{
-    coord::Coord, mesh::settings::GenerateDensityMeshSettings, triangle::Triangle, Scalar,
+    coord::Coord,
+    generator::{
+        error_driven::ErrorDrivenInsertion, points_grid::PointsGrid, random::Random,
+        refinement::Refinement,
+    },
+    mesh::{settings::GenerateDensityMeshSettings, DensityMesh},
+    triangle::Triangle,
+    Scalar,
 };
 use serde::{Deserialize, Serialize};
 
@@ -8,27 +15,92 @@ pub(crate) enum ProcessingChange {
     FindingPoints {
         settings: GenerateDensityMeshSettings,
         tries: usize,
-        /// [(coordinate, value, steepness, local point separation squared)]
+        /// [(coordinate, value, score, local point separation squared)]
+        /// sorted by ascending score.
         remaining: Vec<(Coord, Scalar, Scalar, Scalar)>,
         points: Vec<Coord>,
+        grid: PointsGrid,
+        region: Option<RegionChange>,
+        progress_current: usize,
+        progress_limit: usize,
+    },
+    SamplingPoints {
+        settings: GenerateDensityMeshSettings,
+        random: Random,
+        /// Shuffled visible pixels coordinates, used to start sampling of new areas.
+        remaining: Vec<Coord>,
+        /// Indices of points that might still have free space around them.
+        active: Vec<usize>,
+        points: Vec<Coord>,
+        grid: PointsGrid,
         progress_current: usize,
         progress_limit: usize,
     },
+    InsertingPoints {
+        settings: GenerateDensityMeshSettings,
+        insertion: ErrorDrivenInsertion,
+        progress_limit: usize,
+    },
     Triangulate {
         settings: GenerateDensityMeshSettings,
         points: Vec<Coord>,
+        region: Option<RegionChange>,
+        progress_limit: usize,
+    },
+    Refining {
+        settings: GenerateDensityMeshSettings,
+        refinement: Refinement,
         progress_limit: usize,
     },
     RemoveInvisibleTriangles {
         settings: GenerateDensityMeshSettings,
         points: Vec<Coord>,
         triangles: Vec<Triangle>,
+        /// Known visibility of triangles (`None` means not yet tested).
+        visibility: Vec<Option<bool>>,
         progress_limit: usize,
     },
     Extrude {
         points: Vec<Coord>,
         triangles: Vec<Triangle>,
         size: Scalar,
+        settings: GenerateDensityMeshSettings,
         progress_limit: usize,
     },
+    Stage {
+        settings: GenerateDensityMeshSettings,
+        mesh: DensityMesh,
+        /// Index of registered processing stage.
+        stage: usize,
+        step: usize,
+        steps: usize,
+    },
+}
+
+/// Part of previous triangulation that survives map region change.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct RegionChange {
+    /// Number of points kept from previous triangulation (they occupy first indices).
+    pub kept_points: usize,
+    /// Triangles kept from previous triangulation.
+    pub triangles: Vec<Triangle>,
+    /// Known visibility of kept triangles.
+    pub visibility: Vec<Option<bool>>,
+    /// Corners of removed triangles.
+    pub cavity: Vec<[Coord; 3]>,
+    /// Removed triangles edges shared with kept triangles.
+    pub boundary: Vec<(usize, usize)>,
+    /// Kept points used by removed triangles.
+    pub ring: Vec<usize>,
+    /// Tells if removed triangles are fully surrounded by kept ones.
+    pub enclosed: bool,
+}
+
+/// Triangulation produced by last finished change.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct TriangulationCache {
+    pub settings: GenerateDensityMeshSettings,
+    pub points: Vec<Coord>,
+    pub triangles: Vec<Triangle>,
+    pub visibility: Vec<Option<bool>>,
 }
@@ -0,0 +1,95 @@
+use crate::Scalar;
+use serde::{Deserialize, Serialize};
+
+/// Stage of density mesh generation, in order of execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProgressStage {
+    /// No change is being processed.
+    Idle,
+    /// Placing points at the steepest pixels.
+    FindingPoints,
+    /// Placing points with Poisson disc sampling.
+    SamplingPoints,
+    /// Placing points with the largest approximation error.
+    InsertingPoints,
+    /// Triangulating placed points.
+    Triangulating,
+    /// Inserting Steiner points of Delaunay refinement.
+    Refining,
+    /// Removing invisible triangles.
+    RemovingInvisibleTriangles,
+    /// Extruding mesh outline.
+    Extruding,
+    /// Running registered processing stage with given index.
+    Custom(usize),
+}
+
+impl ProgressStage {
+    /// Returns share of the stage in overall progress, relative to other stages.
+    ///
+    /// # Examples
+    /// ```
+    /// use density_mesh_core::prelude::*;
+    ///
+    /// assert!(ProgressStage::FindingPoints.weight() > ProgressStage::Triangulating.weight());
+    /// assert_eq!(ProgressStage::Idle.weight(), 0.0);
+    /// ```
+    pub fn weight(&self) -> Scalar {
+        match self {
+            Self::Idle => 0.0,
+            Self::FindingPoints | Self::SamplingPoints | Self::InsertingPoints => 6.0,
+            Self::Refining => 2.0,
+            Self::Triangulating
+            | Self::RemovingInvisibleTriangles
+            | Self::Extruding
+            | Self::Custom(_) => 1.0,
+        }
+    }
+
+    /// Returns position of the stage in generation pipeline.
+    pub(crate) fn order(&self) -> usize {
+        match self {
+            Self::Idle => 0,
+            Self::FindingPoints | Self::SamplingPoints | Self::InsertingPoints => 1,
+            Self::Triangulating => 2,
+            Self::Refining => 3,
+            Self::RemovingInvisibleTriangles => 4,
+            Self::Extruding => 5,
+            Self::Custom(index) => 6 + index,
+        }
+    }
+}
+
+/// Progress of density mesh generator.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProgressReport {
+    /// Stage of currently processed change.
+    pub stage: ProgressStage,
+    /// Work done in current stage.
+    pub current: usize,
+    /// Work to do in current stage.
+    pub limit: usize,
+    /// Progress of current stage, in range from 0 to 1.
+    pub stage_progress: Scalar,
+    /// Progress of currently processed change, in range from 0 to 1, where each stage it goes
+    /// through has share proportional to its weight.
+    pub overall_progress: Scalar,
+    /// Number of changes waiting for processing, not counting the current one.
+    pub queued_changes: usize,
+    /// Number of points placed so far by current change.
+    pub points: usize,
+}
+
+impl Default for ProgressReport {
+    fn default() -> Self {
+        Self {
+            stage: ProgressStage::Idle,
+            current: 0,
+            limit: 0,
+            stage_progress: 0.0,
+            overall_progress: 1.0,
+            queued_changes: 0,
+            points: 0,
+        }
+    }
+}
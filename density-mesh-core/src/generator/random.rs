@@ -0,0 +1,39 @@
+use crate::Scalar;
+use serde::{Deserialize, Serialize};
+
+/// Deterministic SplitMix64 random numbers generator, so results do not depend on platform.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns number in range from 0 (inclusive) to 1 (exclusive).
+    pub fn next_scalar(&mut self) -> Scalar {
+        (self.next_u64() >> 40) as Scalar / (1u64 << 24) as Scalar
+    }
+
+    /// Returns index in range from 0 (inclusive) to `count` (exclusive).
+    pub fn next_index(&mut self, count: usize) -> usize {
+        (self.next_u64() % count.max(1) as u64) as usize
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.next_index(i + 1);
+            items.swap(i, j);
+        }
+    }
+}
@@ -0,0 +1,287 @@
+use crate::{
+    coord::Coord,
+    delaunay::{circumcenter, orient, DelaunayTriangulation},
+    map::DensityMap,
+    mesh::settings::GenerateDensityMeshSettings,
+    Scalar,
+};
+use serde::{Deserialize, Serialize};
+
+/// Delaunay refinement, which inserts Steiner points into circumcenters of triangles with too
+/// small angles or too big area, or splits constrained edges encroached by them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Refinement {
+    triangulation: DelaunayTriangulation,
+    /// Tells which triangles are inside of constrained outline (if there is one).
+    inside: Option<Vec<bool>>,
+    /// Triangles left to check.
+    pending: Vec<usize>,
+    checked: usize,
+    inserted: usize,
+    max_points: usize,
+    /// Limit of triangles checked without inserting a point in single step.
+    max_step_rejections: usize,
+    /// Sine of minimal angle.
+    min_angle_sin: Option<Scalar>,
+    max_area: Option<Scalar>,
+}
+
+impl Refinement {
+    /// Create refinement of triangulation.
+    ///
+    /// # Arguments
+    /// * `triangulation` - Refined triangulation.
+    /// * `inside` - Triangles inside of constrained outline, only these get refined.
+    /// * `settings` - Density mesh generation settings.
+    /// * `max_points` - Limit of inserted points.
+    pub fn new(
+        triangulation: DelaunayTriangulation,
+        inside: Option<Vec<bool>>,
+        settings: &GenerateDensityMeshSettings,
+        max_points: usize,
+    ) -> Self {
+        let pending = (0..triangulation.triangles().len()).rev().collect();
+        Self {
+            triangulation,
+            inside,
+            pending,
+            checked: 0,
+            inserted: 0,
+            max_points,
+            max_step_rejections: settings.max_step_rejections.max(1),
+            min_angle_sin: settings
+                .min_angle
+                .map(|v| v.clamp(0.0, 60.0).to_radians().sin()),
+            max_area: settings.max_triangle_area,
+        }
+    }
+
+    /// Returns number of triangulation points.
+    pub fn points_count(&self) -> usize {
+        self.triangulation.points().len()
+    }
+
+    /// Returns progress as `(current, limit)`: triangles checked out of these and triangles left
+    /// to check, or inserted points out of points limit when that one is closer to be reached.
+    pub fn progress(&self) -> (usize, usize) {
+        let checked = (self.checked, self.checked + self.pending.len());
+        let inserted = (self.inserted, self.max_points);
+        if inserted.0 as Scalar * checked.1 as Scalar > checked.0 as Scalar * inserted.1 as Scalar {
+            inserted
+        } else {
+            checked
+        }
+    }
+
+    /// Insert next Steiner point, checking limited number of triangles that need no refinement.
+    ///
+    /// # Returns
+    /// `true` if there is nothing more to refine.
+    pub fn step(&mut self, map: &DensityMap) -> bool {
+        let mut skipped = 0;
+        while let Some(triangle) = self.pending.pop() {
+            if self.inserted >= self.max_points {
+                return true;
+            }
+            self.checked += 1;
+            let point = match &self.inside {
+                Some(inside) if !inside[triangle] => None,
+                _ => self.steiner_point(triangle, map),
+            };
+            let changed = match point.and_then(|point| self.triangulation.insert(point)) {
+                Some((_, changed)) if !changed.is_empty() => changed,
+                _ => {
+                    skipped += 1;
+                    if skipped >= self.max_step_rejections {
+                        return self.pending.is_empty();
+                    }
+                    continue;
+                }
+            };
+            self.inserted += 1;
+            if let Some(inside) = &mut self.inside {
+                // new triangles are placed on the same side of outline as the replaced ones.
+                let flags = changed
+                    .iter()
+                    .map(|(_, parent)| inside[*parent])
+                    .collect::<Vec<_>>();
+                for ((current, _), flag) in changed.iter().zip(flags) {
+                    if *current >= inside.len() {
+                        inside.resize(*current + 1, false);
+                    }
+                    inside[*current] = flag;
+                }
+            }
+            // triangle might still be bad when encroached edge was split instead.
+            if changed.iter().all(|(current, _)| *current != triangle) {
+                self.pending.push(triangle);
+            }
+            self.pending
+                .extend(changed.into_iter().map(|(current, _)| current));
+            return false;
+        }
+        true
+    }
+
+    /// Consume refinement into triangulation and triangles inside of constrained outline.
+    pub fn into_inner(self) -> (DelaunayTriangulation, Option<Vec<bool>>) {
+        (self.triangulation, self.inside)
+    }
+
+    /// Returns point that should be inserted to improve given triangle, if it needs that.
+    fn steiner_point(&self, triangle: usize, map: &DensityMap) -> Option<Coord> {
+        let points = self.triangulation.points();
+        let [a, b, c] = self.triangulation.triangles()[triangle];
+        let (pa, pb, pc) = (points[a], points[b], points[c]);
+        let area = orient(pa, pb, pc) as Scalar * 0.5;
+        if area <= 0.0 {
+            return None;
+        }
+        let center = circumcenter(pa, pb, pc)?;
+        let radius = (pa - center).magnitude();
+        // triangles smaller than map pixels are left as they are.
+        let scale = map.scale().max(1) as Scalar;
+        if radius < scale * 0.5 {
+            return None;
+        }
+        let shortest = (pb - pa)
+            .magnitude()
+            .min((pc - pb).magnitude())
+            .min((pa - pc).magnitude());
+        let too_sharp = self
+            .min_angle_sin
+            .map(|v| shortest < 2.0 * radius * v)
+            .unwrap_or(false);
+        let too_big = self.max_area.map(|v| area > v).unwrap_or(false);
+        if !too_sharp && !too_big {
+            return None;
+        }
+
+        // walk towards circumcenter, but do not go through constrained and bounds edges.
+        let start = (pa + pb + pc) / 3.0;
+        let triangles = self.triangulation.triangles();
+        let neighbors = self.triangulation.neighbors();
+        let mut current = triangle;
+        'walk: for _ in 0..triangles.len() {
+            let corners = triangles[current];
+            for i in 0..3 {
+                let from = corners[i];
+                let to = corners[(i + 1) % 3];
+                let (pf, pt) = (points[from], points[to]);
+                if orient(pf, pt, center) >= 0.0
+                    || orient(start, center, pf) * orient(start, center, pt) > 0.0
+                {
+                    continue;
+                }
+                match neighbors[current][i] {
+                    Some(next) if !self.triangulation.is_constrained(from, to) => {
+                        current = next;
+                        continue 'walk;
+                    }
+                    _ => return self.split_point(from, to, scale),
+                }
+            }
+            break;
+        }
+        // circumcenter that encroaches constrained or bounds edge splits that edge instead.
+        let nearby = std::iter::once(current).chain(neighbors[current].iter().flatten().copied());
+        for index in nearby {
+            let corners = triangles[index];
+            for i in 0..3 {
+                let from = corners[i];
+                let to = corners[(i + 1) % 3];
+                if (neighbors[index][i].is_none() || self.triangulation.is_constrained(from, to))
+                    && (points[from] - center).dot(points[to] - center) < 0.0
+                {
+                    return self.split_point(from, to, scale);
+                }
+            }
+        }
+        Some(center)
+    }
+
+    fn split_point(&self, from: usize, to: usize, min_length: Scalar) -> Option<Coord> {
+        let points = self.triangulation.points();
+        let (from, to) = (points[from], points[to]);
+        if (to - from).magnitude() < min_length {
+            None
+        } else {
+            Some((from + to) * 0.5)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        coord::Coord,
+        generator::{progress_report::ProgressStage, test_utils::*, DensityMeshGenerator},
+        mesh::settings::GenerateDensityMeshSettings,
+    };
+
+    #[test]
+    fn test_refinement_min_angle() {
+        let min_angle = |[a, b, c]: [Coord; 3]| {
+            let angle = |p: Coord, q: Coord, r: Coord| {
+                let (u, v) = ((q - p).normalized(), (r - p).normalized());
+                u.dot(v).clamp(-1.0, 1.0).acos().to_degrees()
+            };
+            angle(a, b, c).min(angle(b, c, a)).min(angle(c, a, b))
+        };
+        for (map, constrain_contour) in [(wavy_map(48), false), (ring_map(64), true)] {
+            let settings = GenerateDensityMeshSettings {
+                points_separation: 4.0.into(),
+                constrain_contour,
+                keep_invisible_triangles: !constrain_contour,
+                ..Default::default()
+            };
+            let unrefined = {
+                let mut generator =
+                    DensityMeshGenerator::new(vec![], map.clone(), settings.clone());
+                generator.process_wait().unwrap();
+                generator.into_mesh().unwrap()
+            };
+            let settings = GenerateDensityMeshSettings {
+                min_angle: Some(25.0),
+                max_triangle_area: Some(40.0),
+                ..settings
+            };
+            let mut generator = DensityMeshGenerator::new(vec![], map, settings);
+            generator.process_wait().unwrap();
+            let mesh = generator.into_mesh().unwrap();
+            assert!(unrefined
+                .triangles
+                .iter()
+                .any(|t| min_angle(corners(&unrefined, t)) < 25.0));
+            for t in &mesh.triangles {
+                let corners = corners(&mesh, t);
+                assert!(min_angle(corners) >= 25.0 - 1.0e-3, "{:?}", corners);
+                assert!(signed_area(corners).abs() <= 40.0 + 1.0e-3, "{:?}", corners);
+            }
+        }
+    }
+
+    #[test]
+    fn test_refinement_progress() {
+        let settings = GenerateDensityMeshSettings {
+            points_separation: 4.0.into(),
+            keep_invisible_triangles: true,
+            min_angle: Some(25.0),
+            ..Default::default()
+        };
+        let mut generator = DensityMeshGenerator::new(vec![], wavy_map(48), settings);
+        let mut reports = vec![];
+        generator
+            .process_wait_tracked(|report| {
+                if report.stage == ProgressStage::Refining {
+                    reports.push(report);
+                }
+            })
+            .unwrap();
+        assert!(reports.len() > 1);
+        assert!(reports.iter().all(|r| r.current <= r.limit));
+        assert!(reports.iter().all(|r| r.limit < 48 * 48));
+        let last = reports.last().unwrap();
+        assert!(last.stage_progress > 0.5, "{:?}", last);
+    }
+}
@@ -0,0 +1,220 @@
+use crate::{
+    map::DensityMap,
+    mesh::{settings::GenerateDensityMeshSettings, DensityMesh, GenerateDensityMeshError},
+};
+use std::{fmt, sync::Arc};
+
+/// User-defined processing stage, which runs on the built (and extruded) density mesh before it
+/// gets published by generator.
+///
+/// Stage work is split into steps, so it is time-sliced and reports progress just like the
+/// built-in stages: each call of `DensityMeshGenerator::process` performs a single step.
+/// Mesh attributes enabled in settings are filled again after the last stage.
+///
+/// # Examples
+/// ```
+/// use density_mesh_core::prelude::*;
+///
+/// /// Moves every point half way towards the mesh center.
+/// struct Shrink;
+///
+/// impl ProcessingStage for Shrink {
+///     fn steps(
+///         &self,
+///         mesh: &DensityMesh,
+///         _: &DensityMap,
+///         _: &GenerateDensityMeshSettings,
+///     ) -> usize {
+///         mesh.points.len()
+///     }
+///
+///     fn step(
+///         &self,
+///         step: usize,
+///         mesh: &mut DensityMesh,
+///         map: &DensityMap,
+///         _: &GenerateDensityMeshSettings,
+///     ) -> Result<(), GenerateDensityMeshError> {
+///         let center = Coord::new(map.width() as Scalar, map.height() as Scalar) * 0.5;
+///         let point = &mut mesh.points[step];
+///         *point = (*point + center) * 0.5;
+///         Ok(())
+///     }
+/// }
+///
+/// let data = (0..256).map(|i| if i % 16 < 8 { 255 } else { 0 }).collect();
+/// let map = DensityMap::new(16, 16, 1, data).unwrap();
+/// let settings = GenerateDensityMeshSettings {
+///     points_separation: 4.0.into(),
+///     ..Default::default()
+/// };
+/// let mut generator = DensityMeshGenerator::new(vec![], map, settings);
+/// generator.add_stage(Shrink);
+/// generator.process_wait().unwrap();
+/// let mesh = generator.mesh().unwrap();
+/// assert!(mesh.points.iter().all(|p| p.x >= 4.0 && p.x <= 12.0));
+/// ```
+pub trait ProcessingStage: Send + Sync {
+    /// Returns number of steps needed to process given mesh.
+    ///
+    /// # Arguments
+    /// * `mesh` - Processed mesh.
+    /// * `map` - Density map.
+    /// * `settings` - Density mesh generation settings.
+    fn steps(
+        &self,
+        mesh: &DensityMesh,
+        map: &DensityMap,
+        settings: &GenerateDensityMeshSettings,
+    ) -> usize;
+
+    /// Perform single step of processing.
+    ///
+    /// # Arguments
+    /// * `step` - Index of the step, from 0 to number of steps.
+    /// * `mesh` - Processed mesh.
+    /// * `map` - Density map.
+    /// * `settings` - Density mesh generation settings.
+    ///
+    /// # Returns
+    /// Ok or generation error, which aborts the change. Aborted change is queued again and
+    /// processed once more, then it gets dropped when it fails again.
+    fn step(
+        &self,
+        step: usize,
+        mesh: &mut DensityMesh,
+        map: &DensityMap,
+        settings: &GenerateDensityMeshSettings,
+    ) -> Result<(), GenerateDensityMeshError>;
+}
+
+/// Processing stages registered in generator, run in order of registration.
+#[derive(Default, Clone)]
+pub(crate) struct Stages(pub Vec<Arc<dyn ProcessingStage>>);
+
+impl fmt::Debug for Stages {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Stages").field(&self.0.len()).finish()
+    }
+}
+
+impl PartialEq for Stages {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && self
+                .0
+                .iter()
+                .zip(other.0.iter())
+                .all(|(a, b)| Arc::ptr_eq(a, b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        coord::Coord,
+        generator::{test_utils::wavy_map, DensityMeshGenerator},
+        mesh::attributes::MeshAttributes,
+        Scalar,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Moves points half way towards the map center, failing given number of times at step 3.
+    struct Shrink(AtomicUsize);
+
+    impl ProcessingStage for Shrink {
+        fn steps(
+            &self,
+            mesh: &DensityMesh,
+            _: &DensityMap,
+            _: &GenerateDensityMeshSettings,
+        ) -> usize {
+            mesh.points.len()
+        }
+
+        fn step(
+            &self,
+            step: usize,
+            mesh: &mut DensityMesh,
+            map: &DensityMap,
+            _: &GenerateDensityMeshSettings,
+        ) -> Result<(), GenerateDensityMeshError> {
+            if step == 3
+                && self
+                    .0
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |f| f.checked_sub(1))
+                    .is_ok()
+            {
+                return Err(GenerateDensityMeshError::NothingCreated);
+            }
+            let center = Coord::new(map.width() as Scalar, map.height() as Scalar) * 0.5;
+            let point = &mut mesh.points[step];
+            *point = (*point + center) * 0.5;
+            Ok(())
+        }
+    }
+
+    /// Generator with published mesh and queued region change, processed by stage failing
+    /// given number of times.
+    fn changed_generator(failures: usize) -> (DensityMeshGenerator, DensityMesh) {
+        let settings = GenerateDensityMeshSettings {
+            points_separation: 4.0.into(),
+            keep_invisible_triangles: true,
+            update_region_margin: 2.0,
+            attributes: MeshAttributes {
+                values: true,
+                uvs: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut generator = DensityMeshGenerator::new(vec![], wavy_map(32), settings.clone());
+        generator.process_wait().unwrap();
+        let unstaged = generator.mesh().unwrap().clone();
+        generator.add_stage(Shrink(failures.into()));
+        generator
+            .change_map(8, 8, 8, 8, vec![255; 64], settings)
+            .unwrap();
+        (generator, unstaged)
+    }
+
+    #[test]
+    fn test_failed_stage_is_retried() {
+        let (mut expected, _) = changed_generator(0);
+        expected.process_wait().unwrap();
+        let expected = expected.mesh().unwrap();
+
+        let (mut generator, unstaged) = changed_generator(1);
+        assert_eq!(
+            generator.process_wait(),
+            Err(GenerateDensityMeshError::NothingCreated)
+        );
+        // failed change keeps published mesh and gets processed again with its region.
+        assert_eq!(generator.mesh().unwrap(), &unstaged);
+        assert!(generator.in_progress());
+        generator.process_wait().unwrap();
+        let mesh = generator.mesh().unwrap();
+        assert_eq!(mesh, expected);
+        assert!(mesh.points.iter().all(|p| p.x >= 8.0 && p.x <= 24.0));
+        // attributes follow points moved by stage.
+        for (i, p) in mesh.points.iter().enumerate() {
+            assert_eq!(mesh.values[i], generator.map().sample_value(*p));
+            assert_eq!(mesh.uvs[i], *p / 32.0);
+        }
+    }
+
+    #[test]
+    fn test_failing_stage_drops_change() {
+        let (mut generator, unstaged) = changed_generator(usize::MAX);
+        for _ in 0..2 {
+            assert_eq!(
+                generator.process_wait(),
+                Err(GenerateDensityMeshError::NothingCreated)
+            );
+        }
+        assert!(!generator.in_progress());
+        generator.process_wait().unwrap();
+        assert_eq!(generator.mesh().unwrap(), &unstaged);
+    }
+}
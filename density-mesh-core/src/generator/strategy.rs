@@ -0,0 +1,184 @@
+use crate::{
+    coord::Coord,
+    generator::visibility::VisibilityTable,
+    map::DensityMap,
+    mesh::{settings::GenerateDensityMeshSettings, triangle_visibility::TriangleVisibility},
+    Scalar,
+};
+use std::{fmt, sync::Arc};
+
+/// Scoring of point candidates of steepness points placement.
+/// Candidates with the highest score are placed first, the ones scored with NaN last.
+///
+/// Implemented for closures taking `(point, value, steepness)`.
+pub trait PointScoring: Send + Sync {
+    /// Returns score of point candidate.
+    ///
+    /// # Arguments
+    /// * `point` - Candidate coordinate.
+    /// * `value` - Density map value at candidate.
+    /// * `steepness` - Density map steepness at candidate.
+    fn score(&self, point: Coord, value: Scalar, steepness: Scalar) -> Scalar;
+}
+
+impl<F> PointScoring for F
+where
+    F: Fn(Coord, Scalar, Scalar) -> Scalar + Send + Sync,
+{
+    fn score(&self, point: Coord, value: Scalar, steepness: Scalar) -> Scalar {
+        self(point, value, steepness)
+    }
+}
+
+/// Default point scoring, which places the steepest candidates first.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SteepnessScoring;
+
+impl PointScoring for SteepnessScoring {
+    fn score(&self, _: Coord, _: Scalar, steepness: Scalar) -> Scalar {
+        steepness
+    }
+}
+
+/// Predicate that decides which triangles are kept when invisible triangles get removed.
+///
+/// Implemented for closures taking `(triangle sample, settings)`.
+pub trait TriangleFilter: Send + Sync {
+    /// Tells if triangle is visible.
+    ///
+    /// # Arguments
+    /// * `triangle` - Sampled triangle.
+    /// * `settings` - Density mesh generation settings.
+    fn is_visible(&self, triangle: &TriangleSample, settings: &GenerateDensityMeshSettings)
+        -> bool;
+}
+
+impl<F> TriangleFilter for F
+where
+    F: Fn(&TriangleSample, &GenerateDensityMeshSettings) -> bool + Send + Sync,
+{
+    fn is_visible(
+        &self,
+        triangle: &TriangleSample,
+        settings: &GenerateDensityMeshSettings,
+    ) -> bool {
+        self(triangle, settings)
+    }
+}
+
+/// Default triangle filter, which uses triangle visibility criterion of settings.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SettingsTriangleFilter;
+
+impl TriangleFilter for SettingsTriangleFilter {
+    fn is_visible(
+        &self,
+        triangle: &TriangleSample,
+        settings: &GenerateDensityMeshSettings,
+    ) -> bool {
+        let (visible, samples, density) = match settings.triangle_visibility {
+            TriangleVisibility::Centroid => (0, 0, 0.0),
+            _ => triangle.coverage(),
+        };
+        settings.triangle_visibility.is_visible(
+            visible,
+            samples,
+            density,
+            triangle.map().sample_value(triangle.centroid()),
+            settings.visibility_threshold,
+        )
+    }
+}
+
+/// Triangle passed to triangle filter, with access to density map pixels it covers.
+pub struct TriangleSample<'a> {
+    corners: [Coord; 3],
+    map: &'a DensityMap,
+    table: &'a VisibilityTable,
+}
+
+impl<'a> TriangleSample<'a> {
+    pub(crate) fn new(
+        corners: [Coord; 3],
+        map: &'a DensityMap,
+        table: &'a VisibilityTable,
+    ) -> Self {
+        Self {
+            corners,
+            map,
+            table,
+        }
+    }
+
+    /// Returns triangle corners.
+    pub fn corners(&self) -> [Coord; 3] {
+        self.corners
+    }
+
+    /// Returns triangle centroid.
+    pub fn centroid(&self) -> Coord {
+        let [a, b, c] = self.corners;
+        (a + b + c) / 3.0
+    }
+
+    /// Returns density map.
+    pub fn map(&self) -> &DensityMap {
+        self.map
+    }
+
+    /// Count map pixels covered by triangle.
+    /// Pixel at `(col, row)` is sampled at `(col * scale, row * scale)`.
+    ///
+    /// # Returns
+    /// `(visible pixels, all pixels, sum of pixel values)`
+    pub fn coverage(&self) -> (usize, usize, Scalar) {
+        let [a, b, c] = self.corners;
+        self.table.count(a, b, c)
+    }
+}
+
+/// Custom strategies of generator. Unset ones use default behaviour.
+#[derive(Default, Clone)]
+pub(crate) struct Strategies {
+    pub point_scoring: Option<Arc<dyn PointScoring>>,
+    pub triangle_filter: Option<Arc<dyn TriangleFilter>>,
+}
+
+impl Strategies {
+    pub fn point_scoring(&self) -> &dyn PointScoring {
+        match &self.point_scoring {
+            Some(scoring) => scoring.as_ref(),
+            None => &SteepnessScoring,
+        }
+    }
+
+    pub fn triangle_filter(&self) -> &dyn TriangleFilter {
+        match &self.triangle_filter {
+            Some(filter) => filter.as_ref(),
+            None => &SettingsTriangleFilter,
+        }
+    }
+}
+
+impl fmt::Debug for Strategies {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Strategies")
+            .field("point_scoring", &self.point_scoring.is_some())
+            .field("triangle_filter", &self.triangle_filter.is_some())
+            .finish()
+    }
+}
+
+impl PartialEq for Strategies {
+    fn eq(&self, other: &Self) -> bool {
+        fn same<T: ?Sized>(a: &Option<Arc<T>>, b: &Option<Arc<T>>) -> bool {
+            match (a, b) {
+                (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+                (None, None) => true,
+                _ => false,
+            }
+        }
+        same(&self.point_scoring, &other.point_scoring)
+            && same(&self.triangle_filter, &other.triangle_filter)
+    }
+}
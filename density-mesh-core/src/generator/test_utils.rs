@@ -0,0 +1,144 @@
+//! Fixtures shared by tests of generator and mesh modules.
+
+use crate::{
+    contour::Contour, coord::Coord, map::DensityMap, mesh::DensityMesh, triangle::Triangle, Scalar,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Smoothly varying map, without flat areas.
+pub fn wavy_map(size: usize) -> DensityMap {
+    let data = (0..size * size)
+        .map(|i| {
+            let x = (i % size) as Scalar;
+            let y = (i / size) as Scalar;
+            let v = ((x / 5.0).sin() + (y / 7.0).cos()) * 0.25 + 0.5;
+            (v * 255.0) as u8
+        })
+        .collect();
+    DensityMap::new(size, size, 1, data).unwrap()
+}
+
+/// Disc with a hole in the middle.
+pub fn ring_map(size: usize) -> DensityMap {
+    let center = size as Scalar * 0.5;
+    let data = (0..size * size)
+        .map(|i| {
+            let p = Coord::new((i % size) as Scalar, (i / size) as Scalar);
+            let distance = (p - Coord::new(center, center)).magnitude();
+            if distance < center * 0.8 && distance > center * 0.3 {
+                255
+            } else {
+                0
+            }
+        })
+        .collect();
+    DensityMap::new(size, size, 1, data).unwrap()
+}
+
+/// Grid of `cells` x `cells` unit cells (skipping given ones), wound like generated meshes.
+pub fn cells_grid(cells: usize, skip: &[usize]) -> DensityMesh {
+    let row = cells + 1;
+    let points = (0..row * row)
+        .map(|i| Coord::new((i % row) as Scalar, (i / row) as Scalar))
+        .collect();
+    let triangles = (0..cells * cells)
+        .filter(|cell| !skip.contains(cell))
+        .flat_map(|cell| {
+            let i = (cell / cells) * row + cell % cells;
+            [
+                Triangle::from([i, i + row + 1, i + 1]),
+                Triangle::from([i, i + row, i + row + 1]),
+            ]
+        })
+        .collect();
+    DensityMesh {
+        points,
+        triangles,
+        ..Default::default()
+    }
+}
+
+/// Tells if point is inside of area enclosed by contours, using even-odd rule.
+pub fn is_enclosed(point: Coord, contours: &[Contour]) -> bool {
+    let mut result = false;
+    for contour in contours {
+        let count = contour.points.len();
+        for i in 0..count {
+            let a = contour.points[i];
+            let b = contour.points[(i + 1) % count];
+            if (a.y > point.y) != (b.y > point.y)
+                && point.x < a.x + (b.x - a.x) * (point.y - a.y) / (b.y - a.y)
+            {
+                result = !result;
+            }
+        }
+    }
+    result
+}
+
+pub fn corners(mesh: &DensityMesh, t: &Triangle) -> [Coord; 3] {
+    [mesh.points[t.a], mesh.points[t.b], mesh.points[t.c]]
+}
+
+pub fn signed_area([a, b, c]: [Coord; 3]) -> Scalar {
+    ((b.x - a.x) * (c.y - a.y) - (c.x - a.x) * (b.y - a.y)) * 0.5
+}
+
+pub fn convex_hull_area(points: &[Coord]) -> Scalar {
+    let mut points = points.to_vec();
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    let mut hull = Vec::<Coord>::with_capacity(points.len() * 2);
+    for pass in 0..2 {
+        let start = hull.len();
+        for p in &points {
+            while hull.len() >= start + 2
+                && signed_area([hull[hull.len() - 2], hull[hull.len() - 1], *p]) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(*p);
+        }
+        hull.pop();
+        if pass == 0 {
+            points.reverse();
+        }
+    }
+    (0..hull.len())
+        .map(|i| {
+            let (a, b) = (hull[i], hull[(i + 1) % hull.len()]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum::<Scalar>()
+        * 0.5
+}
+
+fn triangle_key(t: &Triangle) -> [usize; 3] {
+    let mut result = [t.a, t.b, t.c];
+    result.sort_unstable();
+    result
+}
+
+/// Mesh has no degenerate, inverted or duplicate triangles, every edge is shared by at most two
+/// triangles and triangles exactly cover convex hull of points.
+pub fn assert_valid_cover(mesh: &DensityMesh) {
+    let mut keys = HashSet::new();
+    let mut edges = HashMap::<(usize, usize), usize>::new();
+    let mut orientation = 0.0;
+    let mut area = 0.0;
+    for t in &mesh.triangles {
+        let a = signed_area(corners(mesh, t));
+        assert!(a.abs() > 1.0e-6, "degenerate triangle: {:?}", t);
+        if orientation == 0.0 {
+            orientation = a.signum();
+        }
+        assert_eq!(a.signum(), orientation, "inverted triangle: {:?}", t);
+        area += a.abs();
+        assert!(keys.insert(triangle_key(t)), "duplicate triangle: {:?}", t);
+        for (from, to) in [(t.a, t.b), (t.b, t.c), (t.c, t.a)] {
+            *edges.entry((from.min(to), from.max(to))).or_default() += 1;
+        }
+    }
+    assert!(edges.values().all(|count| *count <= 2), "non-manifold edge");
+    let hull = convex_hull_area(&mesh.points);
+    assert!((area - hull).abs() < hull * 1.0e-4, "{} != {}", area, hull);
+}
@@ -0,0 +1,411 @@
+use super::{test_utils::*, *};
+
+#[test]
+fn test_region_change_is_local() {
+    let settings = GenerateDensityMeshSettings {
+        points_separation: 4.0.into(),
+        keep_invisible_triangles: true,
+        update_region_margin: 2.0,
+        ..Default::default()
+    };
+    let mut generator = DensityMeshGenerator::new(vec![], wavy_map(64), settings.clone());
+    generator.process_wait().unwrap();
+    let before = generator.mesh().unwrap().clone();
+    assert_valid_cover(&before);
+
+    let (col, row, size) = (24, 20, 12);
+    generator
+        .change_map(
+            col,
+            row,
+            size,
+            size,
+            vec![255; size * size],
+            settings.clone(),
+        )
+        .unwrap();
+    generator.process_wait().unwrap();
+    let after = generator.mesh().unwrap().clone();
+    assert_valid_cover(&after);
+    assert_ne!(before, after);
+
+    // dirty rectangle extended by steepness kernel radius and update region margin.
+    let extent =
+        generator.map().steepness_kernel().radius() as Scalar + settings.update_region_margin;
+    let min = Coord::new(col as Scalar - extent, row as Scalar - extent);
+    let max = Coord::new(
+        (col + size) as Scalar + extent,
+        (row + size) as Scalar + extent,
+    );
+    let is_outside = |[a, b, c]: [Coord; 3]| {
+        a.x.max(b.x).max(c.x) < min.x
+            || a.x.min(b.x).min(c.x) > max.x
+            || a.y.max(b.y).max(c.y) < min.y
+            || a.y.min(b.y).min(c.y) > max.y
+    };
+    let key = |corners: [Coord; 3]| {
+        let mut result = corners.map(|p| (p.x.to_bits(), p.y.to_bits()));
+        result.sort_unstable();
+        result
+    };
+    let kept = after
+        .triangles
+        .iter()
+        .map(|t| key(corners(&after, t)))
+        .collect::<HashSet<_>>();
+    let mut outside = 0;
+    for t in &before.triangles {
+        let corners = corners(&before, t);
+        if is_outside(corners) {
+            outside += 1;
+            assert!(
+                kept.contains(&key(corners)),
+                "changed triangle: {:?}",
+                corners
+            );
+        }
+    }
+    assert!(outside > before.triangles.len() / 2);
+    for p in &before.points {
+        if p.x < min.x || p.x > max.x || p.y < min.y || p.y > max.y {
+            assert!(after.points.contains(p));
+        }
+    }
+}
+
+#[test]
+fn test_constrain_contour() {
+    let map = ring_map(64);
+    let contours = extract_contours(&map, 0.01, true);
+    for keep_invisible_triangles in [false, true] {
+        let settings = GenerateDensityMeshSettings {
+            points_separation: 4.0.into(),
+            constrain_contour: true,
+            keep_invisible_triangles,
+            ..Default::default()
+        };
+        let mut generator = DensityMeshGenerator::new(vec![], map.clone(), settings);
+        generator.process_wait().unwrap();
+        let mesh = generator.into_mesh().unwrap();
+        let mut area = 0.0;
+        for t in &mesh.triangles {
+            let corners = corners(&mesh, t);
+            let [a, b, c] = corners;
+            let centroid = (a + b + c) / 3.0;
+            assert!(is_enclosed(centroid, &contours), "outside: {:?}", centroid);
+            area += signed_area(corners).abs();
+        }
+        // triangles fill the whole area enclosed by contours, including the hole outline.
+        let enclosed = contours
+            .iter()
+            .map(|c| c.signed_area())
+            .sum::<Scalar>()
+            .abs();
+        assert!(
+            (area - enclosed).abs() < enclosed * 1.0e-3,
+            "{} != {}",
+            area,
+            enclosed
+        );
+        assert!(!is_enclosed(Coord::new(32.0, 32.0), &contours));
+    }
+}
+
+#[test]
+fn test_extrude_outline_with_hole() {
+    // 3x3 cells grid without the middle cell.
+    let mesh = cells_grid(3, &[4]);
+    let (points, triangles) = (&mesh.points, &mesh.triangles);
+    assert!(mesh
+        .triangles
+        .iter()
+        .all(|t| signed_area(corners(&mesh, t)) < 0.0));
+
+    let size = 0.25;
+    let (offsets, extruded, _) =
+        DensityMeshGenerator::extrude(points, triangles, size, &Default::default()).unwrap();
+    // 12 edges of outer loop and 4 edges of the hole.
+    assert_eq!(offsets.len(), 16);
+    assert_eq!(extruded.len(), 32);
+    let outline = extruded
+        .chunks(2)
+        .map(|pair| (pair[0].b, pair[0].a))
+        .collect::<HashSet<_>>();
+    let is_outer = |i: usize| {
+        let p = points[i];
+        p.x == 0.0 || p.y == 0.0 || p.x == 3.0 || p.y == 3.0
+    };
+    assert_eq!(
+        outline
+            .iter()
+            .filter(|(a, b)| is_outer(*a) && is_outer(*b))
+            .count(),
+        12
+    );
+    let hole = [5, 6, 9, 10];
+    assert_eq!(
+        outline
+            .iter()
+            .filter(|(a, b)| hole.contains(a) && hole.contains(b))
+            .count(),
+        4
+    );
+    for (a, b) in &outline {
+        // every outline edge belongs to exactly one triangle, in the same direction.
+        assert_eq!(
+            triangles
+                .iter()
+                .filter(|t| [(t.a, t.b), (t.b, t.c), (t.c, t.a)].contains(&(*a, *b)))
+                .count(),
+            1
+        );
+    }
+    // outer loop gets pushed away from the mesh and hole loop into the hole.
+    let center = Coord::new(1.5, 1.5);
+    // first triangle of each edge pair is made of edge end, edge start and its extruded point.
+    for triangle in extruded.iter().step_by(2) {
+        let (corner, offset) = (points[triangle.b], offsets[triangle.c - points.len()]);
+        let distance = (offset - corner).magnitude();
+        assert!(
+            distance >= size - 1.0e-4 && distance <= size * 1.5,
+            "{}",
+            distance
+        );
+        if is_outer(triangle.b) {
+            assert!(offset.x < 0.0 || offset.y < 0.0 || offset.x > 3.0 || offset.y > 3.0);
+        } else {
+            assert!((offset - center).x.abs() < 0.5 && (offset - center).y.abs() < 0.5);
+        }
+    }
+
+    let broken = vec![Triangle::from([0, 1, 16])];
+    assert_eq!(
+        DensityMeshGenerator::extrude(points, &broken, size, &Default::default()),
+        Err(GenerateDensityMeshError::InvalidTriangles)
+    );
+}
+
+fn assert_same_points(actual: &[Coord], expected: &[Coord]) {
+    assert_eq!(actual.len(), expected.len(), "{:?}", actual);
+    for e in expected {
+        assert!(
+            actual.iter().any(|a| (*a - *e).magnitude() < 1.0e-4),
+            "{:?} not in {:?}",
+            e,
+            actual
+        );
+    }
+}
+
+#[test]
+fn test_extrude_joins() {
+    let DensityMesh {
+        points, triangles, ..
+    } = cells_grid(1, &[]);
+    let s = 0.25;
+    let extrude = |settings: ExtrudeSettings| {
+        DensityMeshGenerator::extrude(&points, &triangles, s, &settings).unwrap()
+    };
+
+    let (offsets, extruded, _) = extrude(ExtrudeSettings {
+        join: ExtrudeJoin::Miter,
+        ..Default::default()
+    });
+    let miter = [
+        Coord::new(-s, -s),
+        Coord::new(1.0 + s, -s),
+        Coord::new(1.0 + s, 1.0 + s),
+        Coord::new(-s, 1.0 + s),
+    ];
+    assert_same_points(&offsets, &miter);
+    assert_eq!(extruded.len(), 8);
+
+    let bevel = [
+        Coord::new(0.0, -s),
+        Coord::new(-s, 0.0),
+        Coord::new(1.0, -s),
+        Coord::new(1.0 + s, 0.0),
+        Coord::new(1.0 + s, 1.0),
+        Coord::new(1.0, 1.0 + s),
+        Coord::new(0.0, 1.0 + s),
+        Coord::new(-s, 1.0),
+    ];
+    let (offsets, extruded, _) = extrude(ExtrudeSettings {
+        join: ExtrudeJoin::Bevel,
+        ..Default::default()
+    });
+    assert_same_points(&offsets, &bevel);
+    // single triangle cutting each corner and two along each edge.
+    assert_eq!(extruded.len(), 12);
+
+    // miter longer than limit gets beveled.
+    let (offsets, _, _) = extrude(ExtrudeSettings {
+        join: ExtrudeJoin::Miter,
+        miter_limit: 1.2,
+        ..Default::default()
+    });
+    assert_same_points(&offsets, &bevel);
+
+    let (offsets, extruded, _) = extrude(ExtrudeSettings {
+        join: ExtrudeJoin::Round,
+        ..Default::default()
+    });
+    let steps = (std::f32::consts::FRAC_PI_2 / ROUND_JOIN_STEP).ceil() as usize;
+    assert_eq!(offsets.len(), 4 * (steps + 1));
+    assert_eq!(extruded.len(), 4 * steps + 8);
+    for offset in &offsets {
+        // every point lies on quarter of circle around its corner, outside of the square.
+        let corner = Coord::new(offset.x.round(), offset.y.round());
+        assert!(((*offset - corner).magnitude() - s).abs() < 1.0e-4);
+        assert!(
+            (offset.x - corner.x) * (corner.x - 0.5) >= -1.0e-4
+                && (offset.y - corner.y) * (corner.y - 0.5) >= -1.0e-4,
+            "{:?}",
+            offset
+        );
+    }
+    for b in &bevel {
+        assert!(offsets.iter().any(|o| (*o - *b).magnitude() < 1.0e-4));
+    }
+}
+
+#[test]
+fn test_extrude_rings() {
+    let DensityMesh {
+        points, triangles, ..
+    } = cells_grid(1, &[]);
+    let s = 0.3;
+    let (offsets, extruded, falloff) = DensityMeshGenerator::extrude(
+        &points,
+        &triangles,
+        s,
+        &ExtrudeSettings {
+            rings: 3,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(offsets.len(), 12);
+    assert_eq!(falloff.len(), 12);
+    assert_eq!(extruded.len(), 3 * 8);
+    for (ring, chunk) in offsets.chunks(4).zip(falloff.chunks(4)).enumerate() {
+        let (offsets, falloff) = chunk;
+        let factor = (ring + 1) as Scalar / 3.0;
+        assert!(falloff.iter().all(|f| (*f - factor).abs() < 1.0e-6));
+        let d = s * factor;
+        assert_same_points(
+            offsets,
+            &[
+                Coord::new(-d, -d),
+                Coord::new(1.0 + d, -d),
+                Coord::new(1.0 + d, 1.0 + d),
+                Coord::new(-d, 1.0 + d),
+            ],
+        );
+    }
+    // extruded triangles keep winding of the mesh, on both sides of its outline.
+    let all = points
+        .iter()
+        .chain(offsets.iter())
+        .copied()
+        .collect::<Vec<_>>();
+    let mesh = DensityMesh {
+        points: all,
+        triangles: extruded,
+        ..Default::default()
+    };
+    assert!(mesh
+        .triangles
+        .iter()
+        .all(|t| signed_area(corners(&mesh, t)) < 0.0));
+
+    let (offsets, extruded, falloff) = DensityMeshGenerator::extrude(
+        &points,
+        &triangles,
+        s,
+        &ExtrudeSettings {
+            direction: ExtrudeDirection::Inward,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(falloff, vec![1.0; 4]);
+    assert_same_points(
+        &offsets,
+        &[
+            Coord::new(s, s),
+            Coord::new(1.0 - s, s),
+            Coord::new(1.0 - s, 1.0 - s),
+            Coord::new(s, 1.0 - s),
+        ],
+    );
+    let all = points
+        .iter()
+        .chain(offsets.iter())
+        .copied()
+        .collect::<Vec<_>>();
+    let mesh = DensityMesh {
+        points: all,
+        triangles: extruded,
+        ..Default::default()
+    };
+    assert!(mesh
+        .triangles
+        .iter()
+        .all(|t| signed_area(corners(&mesh, t)) < 0.0));
+}
+
+#[test]
+fn test_nan_point_scoring() {
+    let settings = GenerateDensityMeshSettings {
+        points_separation: 4.0.into(),
+        keep_invisible_triangles: true,
+        ..Default::default()
+    };
+    let mut generator = DensityMeshGenerator::new(vec![], wavy_map(32), settings.clone());
+    generator.set_point_scoring(|point: Coord, _, steepness| {
+        if point.x < 16.0 {
+            Scalar::NAN
+        } else {
+            steepness
+        }
+    });
+    generator.process_wait().unwrap();
+    let mesh = generator.mesh().unwrap().clone();
+    assert_valid_cover(&mesh);
+    // candidates scored with NaN are still placed, just after the others.
+    assert!(mesh.points.iter().any(|p| p.x > 0.0 && p.x < 16.0));
+    // region change sorts candidates too.
+    generator
+        .change_map(8, 8, 8, 8, vec![255; 64], settings)
+        .unwrap();
+    generator.process_wait().unwrap();
+    assert_valid_cover(generator.mesh().unwrap());
+}
+
+#[test]
+fn test_constrained_triangle_filter() {
+    let map = ring_map(64);
+    let contours = extract_contours(&map, 0.01, true);
+    let settings = GenerateDensityMeshSettings {
+        points_separation: 4.0.into(),
+        constrain_contour: true,
+        ..Default::default()
+    };
+    let mut generator = DensityMeshGenerator::new(vec![], map, settings);
+    let outline = contours.clone();
+    generator.set_triangle_filter(move |triangle: &TriangleSample, _: &_| {
+        // triangles outside of the outline never get here.
+        assert!(is_enclosed(triangle.centroid(), &outline));
+        triangle.centroid().x < 32.0
+    });
+    generator.process_wait().unwrap();
+    let mesh = generator.into_mesh().unwrap();
+    assert!(!mesh.triangles.is_empty());
+    for t in &mesh.triangles {
+        let [a, b, c] = corners(&mesh, t);
+        let centroid = (a + b + c) / 3.0;
+        assert!(centroid.x < 32.0, "{:?}", centroid);
+        assert!(is_enclosed(centroid, &contours), "{:?}", centroid);
+    }
+}
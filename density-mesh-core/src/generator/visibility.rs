@@ -0,0 +1,345 @@
+use crate::{coord::Coord, map::DensityMap, Scalar};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Tolerance of scanline span ends, so pixels laying exactly on triangle edges are counted.
+const SPAN_EPSILON: Scalar = 1.0e-4;
+
+/// Row-wise prefix sums of visible map pixels (and optionally of pixel values), which allow to
+/// count visible pixels of single triangle scanline in constant time.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct VisibilityTable {
+    threshold: Scalar,
+    width: usize,
+    height: usize,
+    scale: usize,
+    /// [row * (width + 1) + col] - number of visible pixels of the row before column.
+    prefix: Vec<u32>,
+    /// [row * (width + 1) + col] - sum of pixel values of the row before column.
+    density: Option<Vec<f64>>,
+    /// Rows changed since last refresh.
+    dirty: Vec<bool>,
+}
+
+impl VisibilityTable {
+    /// Create table of pixels with value above threshold, with optional sums of pixel values.
+    pub fn new(map: &DensityMap, threshold: Scalar, density: bool) -> Self {
+        let width = map.unscaled_width();
+        let height = map.unscaled_height();
+        let mut result = Self {
+            threshold,
+            width,
+            height,
+            scale: map.scale().max(1),
+            prefix: vec![0; (width + 1) * height],
+            density: if density {
+                Some(vec![0.0; (width + 1) * height])
+            } else {
+                None
+            },
+            dirty: vec![true; height],
+        };
+        result.refresh(map);
+        result
+    }
+
+    /// Tells if table was made for given map and threshold.
+    pub fn matches(&self, map: &DensityMap, threshold: Scalar, density: bool) -> bool {
+        self.threshold == threshold
+            && (!density || self.density.is_some())
+            && self.width == map.unscaled_width()
+            && self.height == map.unscaled_height()
+            && self.scale == map.scale().max(1)
+    }
+
+    /// Mark map rows as changed.
+    pub fn invalidate(&mut self, row: usize, height: usize) {
+        let to = (row + height).min(self.height);
+        for dirty in &mut self.dirty[row.min(to)..to] {
+            *dirty = true;
+        }
+    }
+
+    /// Recompute prefix sums of changed rows.
+    pub fn refresh(&mut self, map: &DensityMap) {
+        if !self.dirty.iter().any(|dirty| *dirty) {
+            return;
+        }
+        let width = self.width;
+        let threshold = self.threshold;
+        let values = map.values();
+        let dirty = &self.dirty;
+        let f = |(row, prefix): (usize, &mut [u32])| {
+            if !dirty[row] {
+                return;
+            }
+            let mut count = 0;
+            prefix[0] = 0;
+            for col in 0..width {
+                if values[row * width + col] > threshold {
+                    count += 1;
+                }
+                prefix[col + 1] = count;
+            }
+        };
+        let g = |(row, density): (usize, &mut [f64])| {
+            if !dirty[row] {
+                return;
+            }
+            let mut sum = 0.0;
+            density[0] = 0.0;
+            for col in 0..width {
+                sum += values[row * width + col] as f64;
+                density[col + 1] = sum;
+            }
+        };
+        #[cfg(feature = "parallel")]
+        {
+            self.prefix
+                .par_chunks_mut(width + 1)
+                .enumerate()
+                .for_each(f);
+            if let Some(density) = &mut self.density {
+                density.par_chunks_mut(width + 1).enumerate().for_each(g);
+            }
+        }
+        #[cfg(not(feature = "parallel"))]
+        {
+            self.prefix.chunks_mut(width + 1).enumerate().for_each(f);
+            if let Some(density) = &mut self.density {
+                density.chunks_mut(width + 1).enumerate().for_each(g);
+            }
+        }
+        for dirty in &mut self.dirty {
+            *dirty = false;
+        }
+    }
+
+    /// Count pixels covered by triangle, by walking its scanlines.
+    /// Pixel at `(col, row)` is sampled at `(col * scale, row * scale)` and pixels outside of
+    /// the map are counted as invisible.
+    ///
+    /// # Returns
+    /// `(visible pixels, all pixels, sum of pixel values)`, where the sum is 0 if table has no
+    /// pixel values sums.
+    pub fn count(&self, a: Coord, b: Coord, c: Coord) -> (usize, usize, Scalar) {
+        let scale = self.scale as Scalar;
+        let min_y = a.y.min(b.y).min(c.y);
+        let max_y = a.y.max(b.y).max(c.y);
+        let from = (min_y / scale - SPAN_EPSILON).ceil() as isize;
+        let to = (max_y / scale + SPAN_EPSILON).floor() as isize;
+        let mut visible = 0;
+        let mut samples = 0;
+        let mut density = 0.0;
+        for row in from..=to {
+            let y = row as Scalar * scale;
+            let (left, right) = match Self::span(a, b, c, y) {
+                Some(span) => span,
+                None => continue,
+            };
+            let left = (left / scale - SPAN_EPSILON).ceil() as isize;
+            let right = (right / scale + SPAN_EPSILON).floor() as isize;
+            if right < left {
+                continue;
+            }
+            samples += (right - left + 1) as usize;
+            if row < 0 || row >= self.height as isize {
+                continue;
+            }
+            let left = left.clamp(0, self.width as isize) as usize;
+            let right = (right + 1).clamp(0, self.width as isize) as usize;
+            if left < right {
+                let offset = row as usize * (self.width + 1);
+                visible += (self.prefix[offset + right] - self.prefix[offset + left]) as usize;
+                if let Some(sums) = &self.density {
+                    density += sums[offset + right] - sums[offset + left];
+                }
+            }
+        }
+        (visible, samples, density as Scalar)
+    }
+
+    /// Returns horizontal range of triangle at given height.
+    fn span(a: Coord, b: Coord, c: Coord, y: Scalar) -> Option<(Scalar, Scalar)> {
+        let mut result: Option<(Scalar, Scalar)> = None;
+        for (from, to) in [(a, b), (b, c), (c, a)] {
+            let (bottom, top) = if from.y <= to.y {
+                (from, to)
+            } else {
+                (to, from)
+            };
+            if y < bottom.y - SPAN_EPSILON || y > top.y + SPAN_EPSILON {
+                continue;
+            }
+            let (left, right) = if top.y - bottom.y <= SPAN_EPSILON {
+                (bottom.x.min(top.x), bottom.x.max(top.x))
+            } else {
+                let factor = ((y - bottom.y) / (top.y - bottom.y)).clamp(0.0, 1.0);
+                let x = bottom.x + (top.x - bottom.x) * factor;
+                (x, x)
+            };
+            result = Some(match result {
+                Some((l, r)) => (l.min(left), r.max(right)),
+                None => (left, right),
+            });
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        generator::strategy::{Strategies, TriangleSample},
+        mesh::{settings::GenerateDensityMeshSettings, triangle_visibility::TriangleVisibility},
+    };
+
+    /// Count pixels with sample point inside of triangle by checking all pixels.
+    ///
+    /// # Returns
+    /// `(visible pixels, all pixels, sum of pixel values)` or `None` if some pixel lays too close
+    /// to triangle edge to tell whether it is covered.
+    fn brute_count(
+        map: &DensityMap,
+        threshold: Scalar,
+        [a, b, c]: [Coord; 3],
+    ) -> Option<(usize, usize, Scalar)> {
+        let (width, height) = (
+            map.unscaled_width() as isize,
+            map.unscaled_height() as isize,
+        );
+        let scale = map.scale() as Scalar;
+        let cross = |a: Coord, b: Coord| a.x * b.y - a.y * b.x;
+        let orientation = cross(b - a, c - a).signum();
+        let mut visible = 0;
+        let mut samples = 0;
+        let mut density = 0.0;
+        // pixels far past the map edges as well.
+        for row in -40..height + 40 {
+            for col in -40..width + 40 {
+                let p = Coord::new(col as Scalar, row as Scalar) * scale;
+                let distances = [(a, b), (b, c), (c, a)].map(|(from, to)| {
+                    let edge = to - from;
+                    cross(edge, p - from) * orientation / edge.magnitude()
+                });
+                if distances.iter().all(|d| *d > -1.0e-3)
+                    && distances.iter().any(|d| d.abs() < 1.0e-3)
+                {
+                    return None;
+                }
+                if distances.iter().any(|d| *d < 0.0) {
+                    continue;
+                }
+                samples += 1;
+                if col >= 0 && row >= 0 && col < width && row < height {
+                    let value = map.values()[(row * width + col) as usize];
+                    density += value;
+                    if value > threshold {
+                        visible += 1;
+                    }
+                }
+            }
+        }
+        Some((visible, samples, density))
+    }
+
+    #[test]
+    fn test_count() {
+        let mut seed = 0x0b5e_55ed_u64;
+        let mut random = move || {
+            seed = seed
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (seed >> 33) as Scalar / (1u64 << 31) as Scalar
+        };
+        for scale in 1..=2 {
+            let (width, height) = (24, 16);
+            let data = (0..width * height)
+                .map(|_| (random() * 255.0) as u8)
+                .collect();
+            let map = DensityMap::new(width, height, scale, data).unwrap();
+            let threshold = 0.5;
+            let table = VisibilityTable::new(&map, threshold, true);
+            // triangles reach up to 20 pixels past the map edges.
+            let size = Coord::new(width as Scalar, height as Scalar) * scale as Scalar;
+            let margin = 20.0 * scale as Scalar;
+            let mut corner = || {
+                Coord::new(
+                    random() * (size.x + margin * 2.0) - margin,
+                    random() * (size.y + margin * 2.0) - margin,
+                )
+            };
+            let mut checked = 0;
+            for _ in 0..300 {
+                let corners = [corner(), corner(), corner()];
+                let [a, b, c] = corners;
+                if let Some((visible, samples, density)) = brute_count(&map, threshold, corners) {
+                    let found = table.count(a, b, c);
+                    assert_eq!((found.0, found.1), (visible, samples), "{:?}", corners);
+                    assert!((found.2 - density).abs() < 1.0e-3, "{:?}", corners);
+                    checked += 1;
+                }
+            }
+            assert!(checked > 200, "{}", checked);
+        }
+    }
+
+    #[test]
+    fn test_sub_pixel_triangle_visibility() {
+        // left half is faintly visible, right half is empty.
+        let data = (0..24).map(|i| if i % 6 < 3 { 100 } else { 0 }).collect();
+        let map = DensityMap::new(6, 4, 1, data).unwrap();
+        let table = VisibilityTable::new(&map, 0.01, true);
+        let strategies = Strategies::default();
+        let filter = strategies.triangle_filter();
+        // covers no pixel anchor.
+        let faint = [
+            Coord::new(0.2, 1.2),
+            Coord::new(0.8, 1.2),
+            Coord::new(0.5, 1.8),
+        ];
+        let empty = faint.map(|p| p + Coord::new(4.0, 0.0));
+        // covers one visible and one empty pixel.
+        let mixed = [
+            Coord::new(1.5, 0.5),
+            Coord::new(3.5, 0.5),
+            Coord::new(2.5, 1.5),
+        ];
+        for [a, b, c] in [faint, empty] {
+            assert_eq!(table.count(a, b, c).1, 0);
+        }
+        let [a, b, c] = mixed;
+        assert_eq!(table.count(a, b, c).1, 2);
+        // (visibility, faint visible, mixed visible)
+        for (triangle_visibility, faint_visible, mixed_visible) in [
+            (TriangleVisibility::CoverageRatio(0.25), true, true),
+            (TriangleVisibility::CoverageRatio(0.5), true, false),
+            (TriangleVisibility::AnyPixel, true, true),
+            (TriangleVisibility::AllPixels, true, false),
+            (TriangleVisibility::Centroid, true, true),
+            (TriangleVisibility::MeanDensity(0.1), true, true),
+            (TriangleVisibility::MeanDensity(0.5), false, false),
+        ] {
+            let settings = GenerateDensityMeshSettings {
+                triangle_visibility,
+                ..Default::default()
+            };
+            let is_visible =
+                |corners| filter.is_visible(&TriangleSample::new(corners, &map, &table), &settings);
+            assert_eq!(
+                is_visible(faint),
+                faint_visible,
+                "{:?}",
+                triangle_visibility
+            );
+            assert!(!is_visible(empty), "{:?}", triangle_visibility);
+            assert_eq!(
+                is_visible(mixed),
+                mixed_visible,
+                "{:?}",
+                triangle_visibility
+            );
+        }
+    }
+}
@@ -1,4 +1,6 @@
+pub mod contour;
 pub mod coord;
+mod delaunay;
 pub mod generator;
 pub mod map;
 pub mod mesh;
@@ -9,7 +11,11 @@ pub type Scalar = f32;
 
 pub mod prelude {
     pub use crate::{
-        coord::*, generator::process_status::*, generator::*, map::*, mesh::points_separation::*,
-        mesh::settings::*, mesh::*, triangle::*, Scalar,
+        contour::*, coord::*, generator::process_status::*, generator::progress_report::*,
+        generator::stage::*, generator::strategy::*, generator::*, map::interpolation::*,
+        map::steepness_kernel::*, map::*, mesh::attributes::*, mesh::extrude::*,
+        mesh::half_edge::*, mesh::heightfield::*, mesh::metrics::*, mesh::points_placement::*,
+        mesh::points_separation::*, mesh::quality::*, mesh::rasterize::*, mesh::settings::*,
+        mesh::spatial_index::*, mesh::triangle_visibility::*, mesh::*, triangle::*, Scalar,
     };
 }
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+/// Interpolation used when sampling density map at fractional coordinates.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Interpolation {
+    /// Value of pixel that contains sampled point.
+    Nearest,
+    /// Linear interpolation between 2x2 nearest pixels.
+    #[default]
+    Bilinear,
+    /// Catmull-Rom interpolation between 4x4 nearest pixels (might overshoot values range).
+    Bicubic,
+}
@@ -0,0 +1,645 @@
+pub mod interpolation;
+pub mod steepness_kernel;
+
+use crate::{
+    coord::Coord,
+    map::{interpolation::Interpolation, steepness_kernel::SteepnessKernel},
+    Scalar,
+};
+use serde::{Deserialize, Serialize};
+
+/// Error thrown during density map generation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DensityMapError {
+    /// Wrong data length.
+    /// (provided, expected)
+    WrongDataLength(usize, usize),
+}
+
+/// Density map that contains density data and steepness per pixel.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DensityMap {
+    width: usize,
+    height: usize,
+    scale: usize,
+    data: Vec<Scalar>,
+    steepness: Vec<Scalar>,
+    #[serde(default)]
+    kernel: SteepnessKernel,
+}
+
+impl DensityMap {
+    /// Create new density map.
+    ///
+    /// # Arguments
+    /// * `width` - Columns.
+    /// * `height` - Rows.
+    /// * `scale` - Scale.
+    /// * `data` - Raw pixel data.
+    ///
+    /// # Returns
+    /// Density map or error.
+    ///
+    /// # Examples
+    /// ```
+    /// use density_mesh_core::prelude::*;
+    ///
+    /// assert!(DensityMap::new(2, 2, 1, vec![0, 1, 2, 3]).is_ok());
+    /// assert_eq!(
+    ///     DensityMap::new(1, 2, 1, vec![0, 1, 2, 3]),
+    ///     Err(DensityMapError::WrongDataLength(4, 2)),
+    /// );
+    /// ```
+    pub fn new(
+        width: usize,
+        height: usize,
+        scale: usize,
+        data: Vec<u8>,
+    ) -> Result<Self, DensityMapError> {
+        Self::new_with_kernel(width, height, scale, data, SteepnessKernel::default())
+    }
+
+    /// Create new density map using given steepness kernel.
+    ///
+    /// # Arguments
+    /// * `width` - Columns.
+    /// * `height` - Rows.
+    /// * `scale` - Scale.
+    /// * `data` - Raw pixel data.
+    /// * `kernel` - Steepness kernel.
+    ///
+    /// # Returns
+    /// Density map or error.
+    ///
+    /// # Examples
+    /// ```
+    /// use density_mesh_core::prelude::*;
+    ///
+    /// let map = DensityMap::new_with_kernel(3, 1, 1, vec![0, 255, 0], SteepnessKernel::Laplacian)
+    ///     .unwrap();
+    /// assert_eq!(map.steepness(), &[0.25, 1.0, 0.25]);
+    /// ```
+    pub fn new_with_kernel(
+        width: usize,
+        height: usize,
+        scale: usize,
+        data: Vec<u8>,
+        kernel: SteepnessKernel,
+    ) -> Result<Self, DensityMapError> {
+        let data = data
+            .into_iter()
+            .map(|v| v as Scalar / 255.0)
+            .collect::<Vec<_>>();
+        Self::new_scalar_with_kernel(width, height, scale, data, kernel)
+    }
+
+    /// Create new density map from 16-bit data.
+    ///
+    /// # Arguments
+    /// * `width` - Columns.
+    /// * `height` - Rows.
+    /// * `scale` - Scale.
+    /// * `data` - Raw 16-bit pixel data.
+    ///
+    /// # Returns
+    /// Density map or error.
+    ///
+    /// # Examples
+    /// ```
+    /// use density_mesh_core::prelude::*;
+    ///
+    /// let map = DensityMap::new_u16(2, 1, 1, vec![0, 65535]).unwrap();
+    /// assert_eq!(map.values(), &[0.0, 1.0]);
+    /// ```
+    pub fn new_u16(
+        width: usize,
+        height: usize,
+        scale: usize,
+        data: Vec<u16>,
+    ) -> Result<Self, DensityMapError> {
+        Self::new_u16_with_kernel(width, height, scale, data, SteepnessKernel::default())
+    }
+
+    /// Create new density map from 16-bit data using given steepness kernel.
+    ///
+    /// # Arguments
+    /// * `width` - Columns.
+    /// * `height` - Rows.
+    /// * `scale` - Scale.
+    /// * `data` - Raw 16-bit pixel data.
+    /// * `kernel` - Steepness kernel.
+    ///
+    /// # Returns
+    /// Density map or error.
+    pub fn new_u16_with_kernel(
+        width: usize,
+        height: usize,
+        scale: usize,
+        data: Vec<u16>,
+        kernel: SteepnessKernel,
+    ) -> Result<Self, DensityMapError> {
+        let data = data
+            .into_iter()
+            .map(|v| v as Scalar / 65535.0)
+            .collect::<Vec<_>>();
+        Self::new_scalar_with_kernel(width, height, scale, data, kernel)
+    }
+
+    /// Create new density map from normalized values (in range from 0 to 1).
+    ///
+    /// # Arguments
+    /// * `width` - Columns.
+    /// * `height` - Rows.
+    /// * `scale` - Scale.
+    /// * `data` - Normalized pixel values.
+    ///
+    /// # Returns
+    /// Density map or error.
+    ///
+    /// # Examples
+    /// ```
+    /// use density_mesh_core::prelude::*;
+    ///
+    /// assert!(DensityMap::new_scalar(2, 1, 1, vec![0.0, 0.5]).is_ok());
+    /// assert_eq!(
+    ///     DensityMap::new_scalar(2, 2, 1, vec![0.0, 0.5]),
+    ///     Err(DensityMapError::WrongDataLength(2, 4)),
+    /// );
+    /// ```
+    pub fn new_scalar(
+        width: usize,
+        height: usize,
+        scale: usize,
+        data: Vec<Scalar>,
+    ) -> Result<Self, DensityMapError> {
+        Self::new_scalar_with_kernel(width, height, scale, data, SteepnessKernel::default())
+    }
+
+    /// Create new density map from normalized values (in range from 0 to 1) using given
+    /// steepness kernel.
+    ///
+    /// # Arguments
+    /// * `width` - Columns.
+    /// * `height` - Rows.
+    /// * `scale` - Scale.
+    /// * `data` - Normalized pixel values.
+    /// * `kernel` - Steepness kernel.
+    ///
+    /// # Returns
+    /// Density map or error.
+    pub fn new_scalar_with_kernel(
+        width: usize,
+        height: usize,
+        scale: usize,
+        data: Vec<Scalar>,
+        kernel: SteepnessKernel,
+    ) -> Result<Self, DensityMapError> {
+        if data.len() == width * height {
+            let steepness = (0..data.len())
+                .map(|i| Self::raw_steepness(i % width, i / width, width, height, &data, kernel))
+                .collect::<Vec<_>>();
+            Ok(Self {
+                width,
+                height,
+                scale,
+                data,
+                steepness,
+                kernel,
+            })
+        } else {
+            Err(DensityMapError::WrongDataLength(data.len(), width * height))
+        }
+    }
+
+    /// Returns scale.
+    pub fn scale(&self) -> usize {
+        self.scale
+    }
+
+    /// Returns scaled width.
+    pub fn width(&self) -> usize {
+        self.width * self.scale.max(1)
+    }
+
+    /// Returns scaled height.
+    pub fn height(&self) -> usize {
+        self.height * self.scale.max(1)
+    }
+
+    /// Returns unscaled width.
+    pub fn unscaled_width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns unscaled height.
+    pub fn unscaled_height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns steepness kernel.
+    pub fn steepness_kernel(&self) -> SteepnessKernel {
+        self.kernel
+    }
+
+    /// Change steepness kernel - this recalculates steepness of all pixels.
+    ///
+    /// # Arguments
+    /// * `kernel` - Steepness kernel.
+    pub fn set_steepness_kernel(&mut self, kernel: SteepnessKernel) {
+        self.kernel = kernel;
+        self.steepness = (0..self.data.len())
+            .map(|i| {
+                Self::raw_steepness(
+                    i % self.width,
+                    i / self.width,
+                    self.width,
+                    self.height,
+                    &self.data,
+                    kernel,
+                )
+            })
+            .collect::<Vec<_>>();
+    }
+
+    /// Returns values buffer.
+    pub fn values(&self) -> &[Scalar] {
+        &self.data
+    }
+
+    /// Returns steepness buffer.
+    pub fn steepness(&self) -> &[Scalar] {
+        &self.steepness
+    }
+
+    /// Returns value at given point or 0 if out of bounds.
+    ///
+    /// # Arguments
+    /// * `point` - (X, Y)
+    pub fn value_at_point(&self, point: (isize, isize)) -> Scalar {
+        let scale = self.scale.max(1) as isize;
+        let col = point.0 / scale;
+        let row = point.1 / scale;
+        if col >= 0 && col < self.width as _ && row >= 0 && row < self.height as _ {
+            self.data
+                .get(row as usize * self.width + col as usize)
+                .copied()
+                .unwrap_or(0.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns steepness at given point or 0 if out of bounds.
+    ///
+    /// # Arguments
+    /// * `point` - (X, Y)
+    pub fn steepness_at_point(&self, point: (isize, isize)) -> Scalar {
+        let scale = self.scale.max(1) as isize;
+        let col = point.0 / scale;
+        let row = point.1 / scale;
+        if col >= 0 && col < self.width as _ && row >= 0 && row < self.height as _ {
+            self.steepness
+                .get(row as usize * self.width + col as usize)
+                .copied()
+                .unwrap_or(0.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns bilinear interpolated value at given point.
+    /// Pixel at `(col, row)` is sampled exactly at `(col * scale, row * scale)`.
+    /// Points outside of the map sample its nearest edge pixels.
+    ///
+    /// # Arguments
+    /// * `point` - Sampled point.
+    ///
+    /// # Examples
+    /// ```
+    /// use density_mesh_core::prelude::*;
+    ///
+    /// let map = DensityMap::new_scalar(2, 1, 2, vec![0.0, 1.0]).unwrap();
+    /// assert_eq!(map.sample_value(Coord::new(0.0, 0.0)), 0.0);
+    /// assert_eq!(map.sample_value(Coord::new(1.0, 0.0)), 0.5);
+    /// assert_eq!(map.sample_value(Coord::new(2.0, 0.0)), 1.0);
+    /// assert_eq!(map.sample_value(Coord::new(2.0, 1.0)), 1.0);
+    /// assert_eq!(map.sample_value(Coord::new(-3.0, 0.0)), 0.0);
+    /// assert_eq!(map.sample_value(Coord::new(5.0, -1.0)), 1.0);
+    /// ```
+    pub fn sample_value(&self, point: Coord) -> Scalar {
+        self.sample_value_interpolated(point, Interpolation::Bilinear)
+    }
+
+    /// Returns bilinear interpolated steepness at given point.
+    /// Pixel at `(col, row)` is sampled exactly at `(col * scale, row * scale)`.
+    /// Points outside of the map sample its nearest edge pixels.
+    ///
+    /// # Arguments
+    /// * `point` - Sampled point.
+    pub fn sample_steepness(&self, point: Coord) -> Scalar {
+        self.sample_steepness_interpolated(point, Interpolation::Bilinear)
+    }
+
+    /// Returns interpolated value at given point, clamped to the map edges.
+    ///
+    /// # Arguments
+    /// * `point` - Sampled point.
+    /// * `interpolation` - Interpolation mode.
+    pub fn sample_value_interpolated(&self, point: Coord, interpolation: Interpolation) -> Scalar {
+        self.sample(&self.data, point, interpolation)
+    }
+
+    /// Returns interpolated steepness at given point, clamped to the map edges.
+    ///
+    /// # Arguments
+    /// * `point` - Sampled point.
+    /// * `interpolation` - Interpolation mode.
+    pub fn sample_steepness_interpolated(
+        &self,
+        point: Coord,
+        interpolation: Interpolation,
+    ) -> Scalar {
+        self.sample(&self.steepness, point, interpolation)
+    }
+
+    /// Returns iterator over values and steepness buffers.
+    ///
+    /// # Examples
+    /// ```
+    /// use density_mesh_core::prelude::*;
+    ///
+    /// let map = DensityMap::new(2, 2, 1, vec![2, 2, 4, 4])
+    ///     .unwrap()
+    ///     .value_steepness_iter()
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(
+    ///     map,
+    ///     vec![
+    ///         (0, 0, 0.007843138, 0.011764706),
+    ///         (1, 0, 0.007843138, 0.011764707),
+    ///         (0, 1, 0.015686275, 0.01633987),
+    ///         (1, 1, 0.015686275, 0.01633987),
+    ///     ],
+    /// );
+    /// ```
+    pub fn value_steepness_iter<'a>(
+        &'a self,
+    ) -> impl Iterator<Item = (usize, usize, Scalar, Scalar)> + 'a {
+        self.data
+            .iter()
+            .zip(self.steepness.iter())
+            .enumerate()
+            .map(move |(i, (v, s))| (i % self.width, i / self.width, *v, *s))
+    }
+
+    /// Change density map region data (replace "pixels") - this recalculates internals.
+    /// Steepness is recalculated also for pixels around region, within steepness kernel radius.
+    ///
+    /// # Arguments
+    /// * `col` - Column index.
+    /// * `row` - Row index.
+    /// * `width` - Number of columns.
+    /// * `height` - Number of rows.
+    /// * `data` - Data to replace with.
+    pub fn change(
+        &mut self,
+        col: usize,
+        row: usize,
+        width: usize,
+        height: usize,
+        data: Vec<u8>,
+    ) -> Result<(), DensityMapError> {
+        let data = data
+            .into_iter()
+            .map(|v| v as Scalar / 255.0)
+            .collect::<Vec<_>>();
+        self.change_scalar(col, row, width, height, data)
+    }
+
+    /// Change density map region with 16-bit data (replace "pixels") - this recalculates
+    /// internals.
+    ///
+    /// # Arguments
+    /// * `col` - Column index.
+    /// * `row` - Row index.
+    /// * `width` - Number of columns.
+    /// * `height` - Number of rows.
+    /// * `data` - 16-bit data to replace with.
+    pub fn change_u16(
+        &mut self,
+        col: usize,
+        row: usize,
+        width: usize,
+        height: usize,
+        data: Vec<u16>,
+    ) -> Result<(), DensityMapError> {
+        let data = data
+            .into_iter()
+            .map(|v| v as Scalar / 65535.0)
+            .collect::<Vec<_>>();
+        self.change_scalar(col, row, width, height, data)
+    }
+
+    /// Change density map region with normalized values (replace "pixels") - this recalculates
+    /// internals.
+    ///
+    /// # Arguments
+    /// * `col` - Column index.
+    /// * `row` - Row index.
+    /// * `width` - Number of columns.
+    /// * `height` - Number of rows.
+    /// * `data` - Normalized values to replace with.
+    pub fn change_scalar(
+        &mut self,
+        col: usize,
+        row: usize,
+        width: usize,
+        height: usize,
+        data: Vec<Scalar>,
+    ) -> Result<(), DensityMapError> {
+        if col == 0 && row == 0 && width == self.width && height == self.height {
+            *self = Self::new_scalar_with_kernel(width, height, self.scale, data, self.kernel)?;
+            Ok(())
+        } else if data.len() == width * height {
+            for (i, v) in data.into_iter().enumerate() {
+                let x = col + i % width;
+                let y = row + i / width;
+                self.data[y * self.width + x] = v;
+            }
+            let radius = self.kernel.radius();
+            let fx = col.saturating_sub(radius);
+            let fy = row.saturating_sub(radius);
+            let tx = (col + width + radius).min(self.width);
+            let ty = (row + height + radius).min(self.height);
+            for row in fy..ty {
+                for col in fx..tx {
+                    self.steepness[row * self.width + col] = Self::raw_steepness(
+                        col,
+                        row,
+                        self.width,
+                        self.height,
+                        &self.data,
+                        self.kernel,
+                    );
+                }
+            }
+            Ok(())
+        } else {
+            Err(DensityMapError::WrongDataLength(data.len(), width * height))
+        }
+    }
+
+    fn raw_steepness(
+        col: usize,
+        row: usize,
+        w: usize,
+        h: usize,
+        data: &[Scalar],
+        kernel: SteepnessKernel,
+    ) -> Scalar {
+        kernel.steepness(col as isize, row as isize, |x, y| {
+            Self::raw_value(x, y, w, h, data)
+        })
+    }
+
+    fn sample(&self, buffer: &[Scalar], point: Coord, interpolation: Interpolation) -> Scalar {
+        if self.width == 0 || self.height == 0 {
+            return 0.0;
+        }
+        let scale = self.scale.max(1) as Scalar;
+        let x = (point.x / scale).clamp(0.0, (self.width - 1) as Scalar);
+        let y = (point.y / scale).clamp(0.0, (self.height - 1) as Scalar);
+        let col = x.floor();
+        let row = y.floor();
+        let fx = x - col;
+        let fy = y - row;
+        let col = col as isize;
+        let row = row as isize;
+        let value = |x: isize, y: isize| {
+            let x = x.clamp(0, self.width as isize - 1) as usize;
+            let y = y.clamp(0, self.height as isize - 1) as usize;
+            buffer[y * self.width + x]
+        };
+        match interpolation {
+            Interpolation::Nearest => value(col, row),
+            Interpolation::Bilinear => {
+                let top = Self::lerp(value(col, row), value(col + 1, row), fx);
+                let bottom = Self::lerp(value(col, row + 1), value(col + 1, row + 1), fx);
+                Self::lerp(top, bottom, fy)
+            }
+            Interpolation::Bicubic => {
+                let cubic_row = |dy| {
+                    Self::cubic(
+                        value(col - 1, row + dy),
+                        value(col, row + dy),
+                        value(col + 1, row + dy),
+                        value(col + 2, row + dy),
+                        fx,
+                    )
+                };
+                Self::cubic(cubic_row(-1), cubic_row(0), cubic_row(1), cubic_row(2), fy)
+            }
+        }
+    }
+
+    #[inline]
+    fn lerp(from: Scalar, to: Scalar, factor: Scalar) -> Scalar {
+        from + (to - from) * factor
+    }
+
+    /// Catmull-Rom spline between `b` and `c`.
+    #[inline]
+    fn cubic(a: Scalar, b: Scalar, c: Scalar, d: Scalar, factor: Scalar) -> Scalar {
+        let t = factor;
+        let t2 = t * t;
+        let t3 = t2 * t;
+        0.5 * (2.0 * b
+            + (c - a) * t
+            + (2.0 * a - 5.0 * b + 4.0 * c - d) * t2
+            + (3.0 * b - 3.0 * c + d - a) * t3)
+    }
+
+    fn raw_value(x: isize, y: isize, w: usize, h: usize, data: &[Scalar]) -> Scalar {
+        if x >= 0 && x < w as _ && y >= 0 && y < h as _ {
+            data[y as usize * w + x as usize]
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_edges() {
+        let map = DensityMap::new_scalar(3, 2, 2, vec![0.2, 0.4, 0.6, 0.8, 1.0, 0.0]).unwrap();
+        let interpolations = [
+            Interpolation::Nearest,
+            Interpolation::Bilinear,
+            Interpolation::Bicubic,
+        ];
+        // (sampled point, expected edge pixel value)
+        let cases = [
+            (Coord::new(0.0, 0.0), 0.2),
+            (Coord::new(4.0, 0.0), 0.6),
+            (Coord::new(0.0, 2.0), 0.8),
+            (Coord::new(4.0, 2.0), 0.0),
+            (Coord::new(-5.0, -5.0), 0.2),
+            (Coord::new(2.0, -1.0), 0.4),
+            (Coord::new(100.0, 0.0), 0.6),
+            (Coord::new(-1.0, 2.0), 0.8),
+            (Coord::new(2.0, 3.5), 1.0),
+            (Coord::new(7.0, 9.0), 0.0),
+        ];
+        for interpolation in &interpolations {
+            for (point, expected) in &cases {
+                let result = map.sample_value_interpolated(*point, *interpolation);
+                assert!(
+                    (result - expected).abs() < 1.0e-6,
+                    "{:?} at {:?}: {} != {}",
+                    interpolation,
+                    point,
+                    result,
+                    expected
+                );
+            }
+        }
+        assert_eq!(
+            map.sample_steepness(Coord::new(-1.0, -1.0)),
+            map.steepness()[0]
+        );
+    }
+
+    #[test]
+    fn test_change_with_kernel_radius() {
+        let kernel = SteepnessKernel::GradientMagnitude(3);
+        let (width, height) = (16, 12);
+        let mut data = (0..(width * height))
+            .map(|i| ((i % width) * 8 + (i / width) * 3) as u8)
+            .collect::<Vec<_>>();
+        let mut map = DensityMap::new_with_kernel(width, height, 1, data.clone(), kernel).unwrap();
+        let before = map.steepness().to_vec();
+
+        let (col, row, w, h) = (6, 5, 2, 3);
+        map.change(col, row, w, h, vec![255; w * h]).unwrap();
+        for y in row..(row + h) {
+            for x in col..(col + w) {
+                data[y * width + x] = 255;
+            }
+        }
+        let expected = DensityMap::new_with_kernel(width, height, 1, data, kernel).unwrap();
+        assert_eq!(map.values(), expected.values());
+        assert_eq!(map.steepness(), expected.steepness());
+        // pixels at kernel radius distance from region see the change.
+        assert_ne!(
+            map.steepness()[row * width + col - 3],
+            before[row * width + col - 3]
+        );
+        assert_ne!(
+            map.steepness()[(row + h + 2) * width + col],
+            before[(row + h + 2) * width + col]
+        );
+    }
+}
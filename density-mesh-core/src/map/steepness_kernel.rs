@@ -0,0 +1,235 @@
+use crate::Scalar;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// Error thrown when steepness kernel cannot be parsed from string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseSteepnessKernelError(pub String);
+
+/// Operator used to calculate density map steepness values.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SteepnessKernel {
+    /// Average of absolute differences between pixels of 2x2 blocks around pixel.
+    /// Good for pixel art, since it does not blur sharp edges.
+    #[default]
+    DifferenceAverage,
+    /// Sobel 3x3 gradient magnitude.
+    Sobel,
+    /// Scharr 3x3 gradient magnitude.
+    Scharr,
+    /// Absolute value of 4-neighbours Laplacian.
+    Laplacian,
+    /// Magnitude of central differences gradient with given radius (in pixels).
+    /// Bigger radius smooths out noise of heightmaps.
+    GradientMagnitude(usize),
+}
+
+impl SteepnessKernel {
+    /// Returns radius of neighbourhood (in pixels) that affects steepness of a pixel.
+    ///
+    /// # Examples
+    /// ```
+    /// use density_mesh_core::prelude::*;
+    ///
+    /// assert_eq!(SteepnessKernel::Sobel.radius(), 1);
+    /// assert_eq!(SteepnessKernel::GradientMagnitude(3).radius(), 3);
+    /// ```
+    pub fn radius(&self) -> usize {
+        match self {
+            Self::GradientMagnitude(radius) => (*radius).max(1),
+            _ => 1,
+        }
+    }
+
+    /// Calculate steepness of a pixel.
+    /// All kernels except `DifferenceAverage` produce values clamped to range from 0 to 1.
+    ///
+    /// # Arguments
+    /// * `col` - Pixel column.
+    /// * `row` - Pixel row.
+    /// * `value` - Function that returns value of pixel at `(col, row)` or 0 if out of bounds.
+    pub fn steepness<F>(&self, col: isize, row: isize, value: F) -> Scalar
+    where
+        F: Fn(isize, isize) -> Scalar,
+    {
+        match self {
+            Self::DifferenceAverage => {
+                let mut result = 0.0;
+                for x in (col - 1)..(col + 1) {
+                    for y in (row - 1)..(row + 1) {
+                        let a = value(x, y);
+                        let b = value(x + 1, y);
+                        let c = value(x + 1, y + 1);
+                        let d = value(x, y + 1);
+                        let ab = (a - b).abs();
+                        let cd = (c - d).abs();
+                        let ac = (a - c).abs();
+                        let bd = (b - d).abs();
+                        let ad = (a - d).abs();
+                        let bc = (b - c).abs();
+                        result += (ab + cd + ac + bd + ad + bc) / 12.0;
+                    }
+                }
+                result
+            }
+            Self::Sobel => (Self::gradient_3x3(col, row, 1.0, 2.0, value) / 4.0).min(1.0),
+            Self::Scharr => (Self::gradient_3x3(col, row, 3.0, 10.0, value) / 16.0).min(1.0),
+            Self::Laplacian => {
+                let result = value(col - 1, row)
+                    + value(col + 1, row)
+                    + value(col, row - 1)
+                    + value(col, row + 1)
+                    - 4.0 * value(col, row);
+                (result.abs() / 4.0).min(1.0)
+            }
+            Self::GradientMagnitude(_) => {
+                let r = self.radius() as isize;
+                let dx = value(col + r, row) - value(col - r, row);
+                let dy = value(col, row + r) - value(col, row - r);
+                (dx * dx + dy * dy).sqrt().min(1.0)
+            }
+        }
+    }
+
+    fn gradient_3x3<F>(col: isize, row: isize, side: Scalar, middle: Scalar, value: F) -> Scalar
+    where
+        F: Fn(isize, isize) -> Scalar,
+    {
+        let dx = side * (value(col + 1, row - 1) - value(col - 1, row - 1))
+            + middle * (value(col + 1, row) - value(col - 1, row))
+            + side * (value(col + 1, row + 1) - value(col - 1, row + 1));
+        let dy = side * (value(col - 1, row + 1) - value(col - 1, row - 1))
+            + middle * (value(col, row + 1) - value(col, row - 1))
+            + side * (value(col + 1, row + 1) - value(col + 1, row - 1));
+        (dx * dx + dy * dy).sqrt()
+    }
+}
+
+impl FromStr for SteepnessKernel {
+    type Err = ParseSteepnessKernelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "difference-average" => Ok(Self::DifferenceAverage),
+            "sobel" => Ok(Self::Sobel),
+            "scharr" => Ok(Self::Scharr),
+            "laplacian" => Ok(Self::Laplacian),
+            "gradient" => Ok(Self::GradientMagnitude(1)),
+            _ => {
+                if let Some(radius) = s.strip_prefix("gradient:") {
+                    radius
+                        .parse::<usize>()
+                        .map(Self::GradientMagnitude)
+                        .map_err(|_| ParseSteepnessKernelError(s.to_owned()))
+                } else {
+                    Err(ParseSteepnessKernelError(s.to_owned()))
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for SteepnessKernel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::DifferenceAverage => write!(f, "difference-average"),
+            Self::Sobel => write!(f, "sobel"),
+            Self::Scharr => write!(f, "scharr"),
+            Self::Laplacian => write!(f, "laplacian"),
+            Self::GradientMagnitude(radius) => write!(f, "gradient:{}", radius),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KERNELS: [SteepnessKernel; 6] = [
+        SteepnessKernel::DifferenceAverage,
+        SteepnessKernel::Sobel,
+        SteepnessKernel::Scharr,
+        SteepnessKernel::Laplacian,
+        SteepnessKernel::GradientMagnitude(1),
+        SteepnessKernel::GradientMagnitude(3),
+    ];
+
+    fn assert_steepness<F>(
+        kernel: SteepnessKernel,
+        col: isize,
+        row: isize,
+        value: F,
+        expected: Scalar,
+    ) where
+        F: Fn(isize, isize) -> Scalar,
+    {
+        let result = kernel.steepness(col, row, value);
+        assert!(
+            (result - expected).abs() < 1.0e-5,
+            "{}: {} != {}",
+            kernel,
+            result,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_flat() {
+        for kernel in &KERNELS {
+            assert_steepness(*kernel, 4, 4, |_, _| 0.5, 0.0);
+        }
+    }
+
+    #[test]
+    fn test_ramp() {
+        let expected = [0.4 / 3.0, 0.2, 0.2, 0.0, 0.2, 0.6];
+        for (kernel, expected) in KERNELS.iter().zip(expected.iter()) {
+            assert_steepness(*kernel, 4, 4, |x, _| x as Scalar * 0.1, *expected);
+            assert_steepness(*kernel, 4, 4, |_, y| y as Scalar * 0.1, *expected);
+        }
+    }
+
+    #[test]
+    fn test_clamped() {
+        let step = |x: isize, _: isize| if x > 0 { 1.0 } else { 0.0 };
+        for kernel in &KERNELS[1..] {
+            let result = kernel.steepness(0, 0, step);
+            assert!(result <= 1.0, "{}: {}", kernel, result);
+        }
+        assert_steepness(SteepnessKernel::Sobel, 0, 0, step, 1.0);
+        assert_steepness(SteepnessKernel::Scharr, 0, 0, step, 1.0);
+        assert_steepness(SteepnessKernel::Laplacian, 0, 0, step, 0.25);
+        // difference average is not clamped.
+        let checker = |x: isize, y: isize| ((x + y) % 2) as Scalar;
+        assert_steepness(SteepnessKernel::DifferenceAverage, 1, 1, checker, 4.0 / 3.0);
+    }
+
+    #[test]
+    fn test_gradient_radius() {
+        assert_eq!(SteepnessKernel::GradientMagnitude(0).radius(), 1);
+        assert_eq!(SteepnessKernel::GradientMagnitude(5).radius(), 5);
+        // gradient only samples pixels at radius distance.
+        let spike = |x: isize, y: isize| if x == 7 && y == 4 { 1.0 } else { 0.0 };
+        assert_steepness(SteepnessKernel::GradientMagnitude(3), 4, 4, spike, 1.0);
+        assert_steepness(SteepnessKernel::GradientMagnitude(2), 4, 4, spike, 0.0);
+    }
+
+    #[test]
+    fn test_parse() {
+        for kernel in &KERNELS {
+            assert_eq!(kernel.to_string().parse::<SteepnessKernel>(), Ok(*kernel));
+        }
+        assert_eq!(
+            "gradient".parse::<SteepnessKernel>(),
+            Ok(SteepnessKernel::GradientMagnitude(1))
+        );
+        assert_eq!(
+            "gradient:x".parse::<SteepnessKernel>(),
+            Err(ParseSteepnessKernelError("gradient:x".to_owned()))
+        );
+        assert_eq!(
+            "prewitt".parse::<SteepnessKernel>(),
+            Err(ParseSteepnessKernelError("prewitt".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+/// Per point attribute channels of density mesh, filled by generator when enabled.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MeshAttributes {
+    /// Density map value.
+    #[serde(default)]
+    pub values: bool,
+    /// Density map steepness.
+    #[serde(default)]
+    pub steepness: bool,
+    /// Texture coordinates (point coordinates divided by map size).
+    #[serde(default)]
+    pub uvs: bool,
+    /// Flag telling if point lays on mesh outline.
+    #[serde(default)]
+    pub outline: bool,
+}
+
+impl MeshAttributes {
+    /// Create attributes with all channels enabled.
+    pub fn all() -> Self {
+        Self {
+            values: true,
+            steepness: true,
+            uvs: true,
+            outline: true,
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use crate::Scalar;
+use serde::{Deserialize, Serialize};
+
+/// Shape of extruded outline at its corners.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExtrudeJoin {
+    /// Sharp corner made of intersecting offset edges, replaced by bevel when it gets longer
+    /// than miter limit.
+    #[default]
+    Miter,
+    /// Corner cut between offset edges ends.
+    Bevel,
+    /// Arc between offset edges ends.
+    Round,
+}
+
+/// Side of mesh outline where extruded rings are placed.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExtrudeDirection {
+    /// Rings surround mesh.
+    #[default]
+    Outward,
+    /// Rings overlap mesh along its outline.
+    Inward,
+}
+
+/// Settings of mesh outline extrusion.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExtrudeSettings {
+    /// Shape of corners.
+    #[serde(default)]
+    pub join: ExtrudeJoin,
+    /// Maximal ratio of miter length to extrude size.
+    #[serde(default = "ExtrudeSettings::default_miter_limit")]
+    pub miter_limit: Scalar,
+    /// Number of rings extruded outline is made of.
+    #[serde(default = "ExtrudeSettings::default_rings")]
+    pub rings: usize,
+    /// Side of outline where rings are placed.
+    #[serde(default)]
+    pub direction: ExtrudeDirection,
+}
+
+impl Default for ExtrudeSettings {
+    fn default() -> Self {
+        Self {
+            join: ExtrudeJoin::default(),
+            miter_limit: Self::default_miter_limit(),
+            rings: Self::default_rings(),
+            direction: ExtrudeDirection::default(),
+        }
+    }
+}
+
+impl ExtrudeSettings {
+    fn default_miter_limit() -> Scalar {
+        4.0
+    }
+
+    fn default_rings() -> usize {
+        1
+    }
+}
@@ -0,0 +1,417 @@
+use crate::{coord::Coord, mesh::GenerateDensityMeshError, triangle::Triangle};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
+
+/// Half-edge adjacency view over mesh triangles.
+///
+/// Half-edge `triangle * 3 + i` goes from triangle corner `i` to corner `i + 1` (where corners
+/// are `a`, `b` and `c`). Edges shared by exactly two triangles are twins of each other, edges
+/// of single triangle are boundary edges and edges shared by more triangles have no twin, but
+/// are not boundary either.
+///
+/// # Examples
+/// ```
+/// use density_mesh_core::prelude::*;
+///
+/// let mesh = DensityMesh {
+///     points: vec![
+///         Coord::new(0.0, 0.0),
+///         Coord::new(1.0, 0.0),
+///         Coord::new(1.0, 1.0),
+///         Coord::new(0.0, 1.0),
+///     ],
+///     triangles: vec![[0, 2, 1].into(), [0, 3, 2].into()],
+///     ..Default::default()
+/// };
+/// let half_edges = mesh.half_edges().unwrap();
+/// assert_eq!(half_edges.triangle_neighbors(0), [Some(1), None, None]);
+/// assert_eq!(half_edges.vertex_neighbors(0), vec![1, 2, 3]);
+/// assert_eq!(half_edges.boundary_edges().len(), 4);
+/// let loops = half_edges.boundary_loops().unwrap();
+/// assert_eq!(loops.len(), 1);
+/// assert_eq!(loops[0].len(), 4);
+/// ```
+#[derive(Debug, Clone)]
+pub struct HalfEdgeMesh<'a> {
+    points: &'a [Coord],
+    triangles: &'a [Triangle],
+    twins: Vec<Option<usize>>,
+    boundary: Vec<bool>,
+    /// Half-edges starting at each point.
+    outgoing: Vec<Vec<usize>>,
+}
+
+impl<'a> HalfEdgeMesh<'a> {
+    /// Create half-edge view.
+    ///
+    /// # Arguments
+    /// * `points` - Mesh points.
+    /// * `triangles` - Mesh triangles.
+    ///
+    /// # Returns
+    /// Half-edge view or error if triangles point to missing points.
+    pub fn new(
+        points: &'a [Coord],
+        triangles: &'a [Triangle],
+    ) -> Result<Self, GenerateDensityMeshError> {
+        if triangles
+            .iter()
+            .any(|t| t.a >= points.len() || t.b >= points.len() || t.c >= points.len())
+        {
+            return Err(GenerateDensityMeshError::InvalidTriangles);
+        }
+        let count = triangles.len() * 3;
+        let mut shared = HashMap::<(usize, usize), Vec<usize>>::with_capacity(count);
+        let mut outgoing = vec![vec![]; points.len()];
+        for half_edge in 0..count {
+            let (from, to) = Self::ends(triangles, half_edge);
+            outgoing[from].push(half_edge);
+            shared
+                .entry((from.min(to), from.max(to)))
+                .or_default()
+                .push(half_edge);
+        }
+        let mut twins = vec![None; count];
+        let mut boundary = vec![false; count];
+        for half_edges in shared.values() {
+            match half_edges.as_slice() {
+                [single] => {
+                    // degenerated triangles have edges without length, which are not boundary.
+                    let (from, to) = Self::ends(triangles, *single);
+                    boundary[*single] = from != to;
+                }
+                [first, second] => {
+                    twins[*first] = Some(*second);
+                    twins[*second] = Some(*first);
+                }
+                _ => {}
+            }
+        }
+        Ok(Self {
+            points,
+            triangles,
+            twins,
+            boundary,
+            outgoing,
+        })
+    }
+
+    /// Returns mesh points.
+    pub fn points(&self) -> &'a [Coord] {
+        self.points
+    }
+
+    /// Returns mesh triangles.
+    pub fn triangles(&self) -> &'a [Triangle] {
+        self.triangles
+    }
+
+    /// Returns number of half-edges.
+    pub fn half_edges_count(&self) -> usize {
+        self.twins.len()
+    }
+
+    /// Returns `(from, to)` points of half-edge.
+    pub fn half_edge(&self, half_edge: usize) -> (usize, usize) {
+        Self::ends(self.triangles, half_edge)
+    }
+
+    /// Returns triangle of half-edge.
+    pub fn triangle(&self, half_edge: usize) -> usize {
+        half_edge / 3
+    }
+
+    /// Returns next half-edge of the same triangle.
+    pub fn next(&self, half_edge: usize) -> usize {
+        half_edge - half_edge % 3 + (half_edge + 1) % 3
+    }
+
+    /// Returns previous half-edge of the same triangle.
+    pub fn prev(&self, half_edge: usize) -> usize {
+        half_edge - half_edge % 3 + (half_edge + 2) % 3
+    }
+
+    /// Returns half-edge of neighbour triangle sharing the same edge.
+    pub fn twin(&self, half_edge: usize) -> Option<usize> {
+        self.twins[half_edge]
+    }
+
+    /// Tells if half-edge lays on mesh boundary.
+    pub fn is_boundary(&self, half_edge: usize) -> bool {
+        self.boundary[half_edge]
+    }
+
+    /// Returns neighbour triangles sharing edges `a-b`, `b-c` and `c-a` of given triangle.
+    pub fn triangle_neighbors(&self, triangle: usize) -> [Option<usize>; 3] {
+        let first = triangle * 3;
+        [0, 1, 2].map(|i| self.twins[first + i].map(|twin| twin / 3))
+    }
+
+    /// Returns half-edges starting at given point.
+    pub fn outgoing(&self, point: usize) -> &[usize] {
+        &self.outgoing[point]
+    }
+
+    /// Returns sorted triangles using given point.
+    pub fn vertex_triangles(&self, point: usize) -> Vec<usize> {
+        let mut result = self.outgoing[point]
+            .iter()
+            .map(|half_edge| half_edge / 3)
+            .collect::<Vec<_>>();
+        result.sort_unstable();
+        result.dedup();
+        result
+    }
+
+    /// Returns sorted points connected by an edge with given point (its one-ring).
+    pub fn vertex_neighbors(&self, point: usize) -> Vec<usize> {
+        let mut result = self.outgoing[point]
+            .iter()
+            .flat_map(|half_edge| {
+                let (_, to) = self.half_edge(*half_edge);
+                let (from, _) = self.half_edge(self.prev(*half_edge));
+                [to, from]
+            })
+            .collect::<Vec<_>>();
+        result.sort_unstable();
+        result.dedup();
+        result
+    }
+
+    /// Tells if point lays on mesh boundary.
+    pub fn is_boundary_vertex(&self, point: usize) -> bool {
+        self.outgoing[point]
+            .iter()
+            .any(|half_edge| self.boundary[*half_edge] || self.boundary[self.prev(*half_edge)])
+    }
+
+    /// Returns boundary half-edges.
+    pub fn boundary_edges(&self) -> Vec<usize> {
+        (0..self.boundary.len())
+            .filter(|half_edge| self.boundary[*half_edge])
+            .collect()
+    }
+
+    /// Connect boundary half-edges into closed loops. Points shared by multiple loops continue
+    /// with the edge that makes the smallest turn inside the mesh.
+    ///
+    /// # Returns
+    /// Loops of consecutive half-edges or error when boundary edges have no length or do not
+    /// form closed loops.
+    pub fn boundary_loops(&self) -> Result<Vec<Vec<usize>>, GenerateDensityMeshError> {
+        let edges = self.boundary_edges();
+        if edges.iter().any(|half_edge| {
+            let (from, to) = self.half_edge(*half_edge);
+            (self.points[to] - self.points[from]).sqr_magnitude() <= 0.0
+        }) {
+            return Err(GenerateDensityMeshError::InvalidOutline);
+        }
+        let mut next = HashMap::with_capacity(edges.len());
+        let mut taken = HashSet::with_capacity(edges.len());
+        for half_edge in &edges {
+            let (from, to) = self.half_edge(*half_edge);
+            let back = self.points[from] - self.points[to];
+            let turn = |candidate: usize| {
+                let forward = self.points[self.half_edge(candidate).1] - self.points[to];
+                let angle = (back.x * forward.y - back.y * forward.x).atan2(back.dot(forward));
+                if angle <= 0.0 {
+                    angle + std::f32::consts::TAU
+                } else {
+                    angle
+                }
+            };
+            let found = self.outgoing[to]
+                .iter()
+                .copied()
+                .filter(|candidate| self.boundary[*candidate] && !taken.contains(candidate))
+                .min_by(|a, b| turn(*a).partial_cmp(&turn(*b)).unwrap_or(Ordering::Equal))
+                .ok_or(GenerateDensityMeshError::InvalidOutline)?;
+            taken.insert(found);
+            next.insert(*half_edge, found);
+        }
+        let mut visited = HashSet::with_capacity(edges.len());
+        let mut result = vec![];
+        for start in edges {
+            if visited.contains(&start) {
+                continue;
+            }
+            let mut current = start;
+            let mut boundary_loop = vec![];
+            while visited.insert(current) {
+                boundary_loop.push(current);
+                current = next[&current];
+            }
+            result.push(boundary_loop);
+        }
+        Ok(result)
+    }
+
+    fn ends(triangles: &[Triangle], half_edge: usize) -> (usize, usize) {
+        let t = &triangles[half_edge / 3];
+        match half_edge % 3 {
+            0 => (t.a, t.b),
+            1 => (t.b, t.c),
+            _ => (t.c, t.a),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generator::test_utils::cells_grid, mesh::DensityMesh, Scalar};
+
+    /// Compare half-edge view with neighbourhood found by scanning all triangles.
+    fn assert_adjacency(mesh: &DensityMesh) {
+        let half_edges = mesh.half_edges().unwrap();
+        let edges = |t: &Triangle| [(t.a, t.b), (t.b, t.c), (t.c, t.a)];
+        assert_eq!(half_edges.half_edges_count(), mesh.triangles.len() * 3);
+        for half_edge in 0..half_edges.half_edges_count() {
+            let (from, to) = half_edges.half_edge(half_edge);
+            let triangle = half_edges.triangle(half_edge);
+            assert_eq!(edges(&mesh.triangles[triangle])[half_edge % 3], (from, to));
+            let next = half_edges.next(half_edge);
+            assert_eq!(half_edges.triangle(next), triangle);
+            assert_eq!(half_edges.half_edge(next).0, to);
+            assert_eq!(half_edges.prev(next), half_edge);
+            assert_eq!(half_edges.next(half_edges.next(next)), half_edge);
+            let reversed = mesh
+                .triangles
+                .iter()
+                .enumerate()
+                .filter(|(_, t)| edges(t).contains(&(to, from)))
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>();
+            match half_edges.twin(half_edge) {
+                Some(twin) => {
+                    assert_eq!(reversed, vec![half_edges.triangle(twin)]);
+                    assert_eq!(half_edges.half_edge(twin), (to, from));
+                    assert_eq!(half_edges.twin(twin), Some(half_edge));
+                    assert!(!half_edges.is_boundary(half_edge));
+                }
+                None => {
+                    assert!(reversed.is_empty());
+                    assert!(half_edges.is_boundary(half_edge));
+                }
+            }
+        }
+        for point in 0..mesh.points.len() {
+            let triangles = (0..mesh.triangles.len())
+                .filter(|i| {
+                    let t = &mesh.triangles[*i];
+                    t.a == point || t.b == point || t.c == point
+                })
+                .collect::<Vec<_>>();
+            let mut neighbors = triangles
+                .iter()
+                .flat_map(|i| {
+                    let t = &mesh.triangles[*i];
+                    [t.a, t.b, t.c]
+                })
+                .filter(|i| *i != point)
+                .collect::<Vec<_>>();
+            neighbors.sort_unstable();
+            neighbors.dedup();
+            assert_eq!(half_edges.vertex_triangles(point), triangles);
+            assert_eq!(half_edges.vertex_neighbors(point), neighbors);
+        }
+    }
+
+    /// Returns loops of `(from, to)` points, checking that they are closed.
+    fn boundary_loops(half_edges: &HalfEdgeMesh) -> Vec<Vec<(usize, usize)>> {
+        let loops = half_edges
+            .boundary_loops()
+            .unwrap()
+            .into_iter()
+            .map(|l| {
+                l.into_iter()
+                    .map(|half_edge| half_edges.half_edge(half_edge))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        for l in &loops {
+            for (i, (_, to)) in l.iter().enumerate() {
+                assert_eq!(*to, l[(i + 1) % l.len()].0);
+            }
+        }
+        loops
+    }
+
+    #[test]
+    fn test_quad() {
+        let mesh = cells_grid(1, &[]);
+        assert_adjacency(&mesh);
+        let half_edges = mesh.half_edges().unwrap();
+        // diagonal is the only shared edge.
+        assert_eq!(half_edges.twin(0), Some(5));
+        assert_eq!(half_edges.triangle_neighbors(0), [Some(1), None, None]);
+        assert_eq!(half_edges.triangle_neighbors(1), [None, None, Some(0)]);
+        assert_eq!(half_edges.boundary_edges(), vec![1, 2, 3, 4]);
+        assert_eq!(half_edges.vertex_neighbors(0), vec![1, 2, 3]);
+        assert_eq!(half_edges.vertex_neighbors(1), vec![0, 3]);
+        assert_eq!(half_edges.outgoing(3), &[1, 5]);
+        assert!((0..4).all(|point| half_edges.is_boundary_vertex(point)));
+        let loops = boundary_loops(&half_edges);
+        assert_eq!(loops.len(), 1);
+        assert_eq!(loops[0].len(), 4);
+    }
+
+    #[test]
+    fn test_with_hole() {
+        let full = cells_grid(3, &[]);
+        assert_adjacency(&full);
+        let half_edges = full.half_edges().unwrap();
+        let inner = [5, 6, 9, 10];
+        for point in 0..full.points.len() {
+            assert_eq!(
+                half_edges.is_boundary_vertex(point),
+                !inner.contains(&point)
+            );
+        }
+        assert_eq!(half_edges.vertex_neighbors(5), vec![0, 1, 4, 6, 9, 10]);
+        assert_eq!(boundary_loops(&half_edges).len(), 1);
+
+        let mesh = cells_grid(3, &[4]);
+        assert_adjacency(&mesh);
+        let half_edges = mesh.half_edges().unwrap();
+        assert!((0..mesh.points.len()).all(|point| half_edges.is_boundary_vertex(point)));
+        assert_eq!(half_edges.boundary_edges().len(), 16);
+        assert_eq!(half_edges.vertex_neighbors(5), vec![0, 1, 4, 6, 9]);
+        let mut loops = boundary_loops(&half_edges);
+        loops.sort_by_key(|l| l.len());
+        assert_eq!(loops.iter().map(|l| l.len()).collect::<Vec<_>>(), [4, 12]);
+        assert!(loops[0].iter().all(|(from, _)| inner.contains(from)));
+        // loops around the mesh and around the hole go in opposite directions.
+        let area = |l: &[(usize, usize)]| -> Scalar {
+            l.iter()
+                .map(|(from, to)| {
+                    let (a, b) = (mesh.points[*from], mesh.points[*to]);
+                    a.x * b.y - b.x * a.y
+                })
+                .sum()
+        };
+        assert!(area(&loops[0]) * area(&loops[1]) < 0.0);
+        assert_eq!(area(&loops[1]).abs(), 18.0);
+        assert_eq!(area(&loops[0]).abs(), 2.0);
+
+        let broken = vec![Triangle::from([0, 1, 16])];
+        assert_eq!(
+            HalfEdgeMesh::new(&mesh.points, &broken).err(),
+            Some(GenerateDensityMeshError::InvalidTriangles)
+        );
+        // edge without length can not be part of outline.
+        let points = vec![
+            Coord::new(0.0, 0.0),
+            Coord::new(0.0, 0.0),
+            Coord::new(1.0, 1.0),
+        ];
+        let triangles = vec![Triangle::from([0, 2, 1])];
+        let half_edges = HalfEdgeMesh::new(&points, &triangles).unwrap();
+        assert_eq!(
+            half_edges.boundary_loops(),
+            Err(GenerateDensityMeshError::InvalidOutline)
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{
+    map::DensityMap,
+    mesh::{DensityMesh, GenerateDensityMeshError},
+    triangle::Triangle,
+    Scalar,
+};
+use serde::{Deserialize, Serialize};
+
+/// 3D mesh made of density mesh lifted by density map values.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HeightfieldMesh {
+    /// List of `[x, y, z]` points, where `z` is the height.
+    pub points: Vec<[Scalar; 3]>,
+    /// List of smooth normals of points, pointing towards positive height.
+    pub normals: Vec<[Scalar; 3]>,
+    /// List of triangles.
+    pub triangles: Vec<Triangle>,
+}
+
+impl HeightfieldMesh {
+    /// Create heightfield mesh.
+    ///
+    /// # Arguments
+    /// * `mesh` - Density mesh.
+    /// * `map` - Density map which values are used as points height.
+    /// * `height_scale` - Height of points with density value of 1.
+    ///
+    /// # Returns
+    /// Heightfield mesh or error if triangles point to missing points.
+    ///
+    /// # Examples
+    /// ```
+    /// use density_mesh_core::prelude::*;
+    ///
+    /// let map = DensityMap::new(2, 2, 1, vec![0, 0, 255, 255]).unwrap();
+    /// let mesh = DensityMesh {
+    ///     points: vec![
+    ///         Coord::new(0.0, 0.0),
+    ///         Coord::new(1.0, 0.0),
+    ///         Coord::new(1.0, 1.0),
+    ///         Coord::new(0.0, 1.0),
+    ///     ],
+    ///     triangles: vec![[0, 2, 1].into(), [0, 3, 2].into()],
+    ///     ..Default::default()
+    /// };
+    /// let heightfield = HeightfieldMesh::new(&mesh, &map, 1.0).unwrap();
+    /// assert_eq!(heightfield.points[2], [1.0, 1.0, 1.0]);
+    /// let [x, y, z] = heightfield.normals[0];
+    /// assert!(x.abs() < 1.0e-6);
+    /// assert!((y - -(0.5 as Scalar).sqrt()).abs() < 1.0e-6);
+    /// assert!((z - (0.5 as Scalar).sqrt()).abs() < 1.0e-6);
+    /// ```
+    pub fn new(
+        mesh: &DensityMesh,
+        map: &DensityMap,
+        height_scale: Scalar,
+    ) -> Result<Self, GenerateDensityMeshError> {
+        let count = mesh.points.len();
+        if mesh
+            .triangles
+            .iter()
+            .any(|t| t.a >= count || t.b >= count || t.c >= count)
+        {
+            return Err(GenerateDensityMeshError::InvalidTriangles);
+        }
+        let points = mesh
+            .points
+            .iter()
+            .map(|p| [p.x, p.y, map.sample_value(*p) * height_scale])
+            .collect::<Vec<_>>();
+        let mut normals = vec![[0.0; 3]; points.len()];
+        for triangle in &mesh.triangles {
+            let (a, b, c) = (points[triangle.a], points[triangle.b], points[triangle.c]);
+            let ab = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+            let ac = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+            // length of cross product is proportional to triangle area, so bigger triangles
+            // have more influence on the smooth normal.
+            let mut normal = [
+                ab[1] * ac[2] - ab[2] * ac[1],
+                ab[2] * ac[0] - ab[0] * ac[2],
+                ab[0] * ac[1] - ab[1] * ac[0],
+            ];
+            // heightfield never overhangs, so upward normal does not depend on winding.
+            if normal[2] < 0.0 {
+                normal = [-normal[0], -normal[1], -normal[2]];
+            }
+            for index in [triangle.a, triangle.b, triangle.c] {
+                let n = &mut normals[index];
+                n[0] += normal[0];
+                n[1] += normal[1];
+                n[2] += normal[2];
+            }
+        }
+        for n in &mut normals {
+            let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+            *n = if length > 0.0 {
+                [n[0] / length, n[1] / length, n[2] / length]
+            } else {
+                [0.0, 0.0, 1.0]
+            };
+        }
+        Ok(Self {
+            points,
+            normals,
+            triangles: mesh.triangles.clone(),
+        })
+    }
+}
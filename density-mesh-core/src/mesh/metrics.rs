@@ -0,0 +1,109 @@
+use crate::{
+    map::DensityMap,
+    mesh::{
+        rasterize::{rasterize, RasterizeSettings},
+        DensityMesh, GenerateDensityMeshError,
+    },
+    Scalar,
+};
+use serde::{Deserialize, Serialize};
+
+/// Report of how well density mesh approximates density map it was generated from.
+///
+/// Mesh height is density map value sampled at triangle corners and interpolated between them.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApproximationMetrics {
+    /// Number of map pixels.
+    pub pixels: usize,
+    /// Number of pixels covered by triangles.
+    pub covered_pixels: usize,
+    /// Number of pixels with value above visibility threshold.
+    pub visible_pixels: usize,
+    /// Root mean square of height error over covered pixels.
+    pub rms_error: Scalar,
+    /// Maximal height error over covered pixels.
+    pub max_error: Scalar,
+    /// Number of visible pixels not covered by any triangle.
+    pub uncovered_visible_pixels: usize,
+    /// Number of invisible pixels covered by triangles (overdraw).
+    pub covered_invisible_pixels: usize,
+}
+
+impl ApproximationMetrics {
+    /// Measure approximation error of density mesh.
+    ///
+    /// # Arguments
+    /// * `mesh` - Density mesh.
+    /// * `map` - Approximated density map.
+    /// * `visibility_threshold` - Pixels with value above it are visible.
+    ///
+    /// # Returns
+    /// Metrics or error if triangles point to missing points.
+    ///
+    /// # Examples
+    /// ```
+    /// use density_mesh_core::prelude::*;
+    ///
+    /// let map = DensityMap::new(3, 3, 1, vec![255, 255, 255, 255, 0, 0, 0, 0, 0]).unwrap();
+    /// let mesh = DensityMesh {
+    ///     points: vec![Coord::new(0.0, 0.0), Coord::new(2.0, 0.0), Coord::new(0.0, 2.0)],
+    ///     triangles: vec![[0, 2, 1].into()],
+    ///     ..Default::default()
+    /// };
+    /// let metrics = ApproximationMetrics::new(&mesh, &map, 0.01).unwrap();
+    /// assert_eq!(metrics.pixels, 9);
+    /// assert_eq!(metrics.covered_pixels, 6);
+    /// assert_eq!(metrics.visible_pixels, 4);
+    /// assert_eq!(metrics.max_error, 0.5);
+    /// assert_eq!(metrics.uncovered_visible_pixels, 0);
+    /// assert_eq!(metrics.covered_invisible_pixels, 2);
+    /// ```
+    pub fn new(
+        mesh: &DensityMesh,
+        map: &DensityMap,
+        visibility_threshold: Scalar,
+    ) -> Result<Self, GenerateDensityMeshError> {
+        let width = map.unscaled_width();
+        let height = map.unscaled_height();
+        let settings = RasterizeSettings::default();
+        let coverage = rasterize(mesh, width, height, map.scale(), None, &settings)?;
+        let heights = mesh
+            .points
+            .iter()
+            .map(|p| map.sample_value(*p))
+            .collect::<Vec<_>>();
+        let approximation = rasterize(mesh, width, height, map.scale(), Some(&heights), &settings)?;
+        let mut result = Self {
+            pixels: width * height,
+            ..Default::default()
+        };
+        let mut squared_error = 0.0;
+        let iter = map
+            .values()
+            .iter()
+            .zip(coverage.values())
+            .zip(approximation.values());
+        for ((value, covered), approximated) in iter {
+            let covered = *covered > 0.0;
+            let visible = *value > visibility_threshold;
+            if visible {
+                result.visible_pixels += 1;
+            }
+            if covered {
+                result.covered_pixels += 1;
+                let error = (value - approximated).abs();
+                squared_error += error * error;
+                result.max_error = result.max_error.max(error);
+            }
+            match (visible, covered) {
+                (true, false) => result.uncovered_visible_pixels += 1,
+                (false, true) => result.covered_invisible_pixels += 1,
+                _ => {}
+            }
+        }
+        if result.covered_pixels > 0 {
+            result.rms_error = (squared_error / result.covered_pixels as Scalar).sqrt();
+        }
+        Ok(result)
+    }
+}
@@ -1,7 +1,23 @@
+pub mod attributes;
+pub mod extrude;
+pub mod half_edge;
+pub mod heightfield;
+pub mod metrics;
+pub mod points_placement;
 pub mod points_separation;
+pub mod quality;
+pub mod rasterize;
 pub mod settings;
+pub mod spatial_index;
+pub mod triangle_visibility;
 
-use crate::{coord::Coord, map::DensityMapError, triangle::Triangle};
+use crate::{
+    coord::Coord,
+    map::DensityMapError,
+    mesh::{half_edge::HalfEdgeMesh, spatial_index::SpatialIndex},
+    triangle::Triangle,
+    Scalar,
+};
 use serde::{Deserialize, Serialize};
 
 /// Error thrown during density mesh generation.
@@ -15,6 +31,16 @@ pub enum GenerateDensityMeshError {
     FailedTriangulation,
     /// There is no density mesh created.
     NothingCreated,
+    /// Mesh outline could not be found, because of invalid or inconsistently wound triangles.
+    InvalidOutline,
+    /// Triangles point to missing points.
+    InvalidTriangles,
+    /// Wrong length of per point attribute.
+    /// (provided, expected)
+    WrongAttributeLength(usize, usize),
+    /// Constrained edge could not be inserted into triangulation.
+    /// (from, to)
+    FailedConstrainedEdge(Coord, Coord),
 }
 
 /// Density mesh.
@@ -24,4 +50,38 @@ pub struct DensityMesh {
     pub points: Vec<Coord>,
     /// List of triangles.
     pub triangles: Vec<Triangle>,
+    /// Extrude falloff of each point: 0 on the original outline (and inside of mesh) and 1 on
+    /// the last extruded ring. Empty when mesh is not extruded.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub falloff: Vec<Scalar>,
+    /// Density map value of each point. Empty unless enabled in mesh attributes settings.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<Scalar>,
+    /// Density map steepness of each point. Empty unless enabled in mesh attributes settings.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steepness: Vec<Scalar>,
+    /// Texture coordinates of each point. Empty unless enabled in mesh attributes settings.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub uvs: Vec<Coord>,
+    /// Tells if point lays on mesh outline. Empty unless enabled in mesh attributes settings.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outline: Vec<bool>,
+}
+
+impl DensityMesh {
+    /// Create half-edge adjacency view of mesh.
+    ///
+    /// # Returns
+    /// Half-edge view or error if triangles point to missing points.
+    pub fn half_edges(&self) -> Result<HalfEdgeMesh<'_>, GenerateDensityMeshError> {
+        HalfEdgeMesh::new(&self.points, &self.triangles)
+    }
+
+    /// Create spatial index of mesh triangles and points.
+    ///
+    /// # Returns
+    /// Spatial index or error if triangles point to missing points.
+    pub fn spatial_index(&self) -> Result<SpatialIndex<'_>, GenerateDensityMeshError> {
+        SpatialIndex::new(&self.points, &self.triangles)
+    }
 }
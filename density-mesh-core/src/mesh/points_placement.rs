@@ -0,0 +1,96 @@
+use crate::{contour::ContourSimplification, Scalar};
+use serde::{Deserialize, Serialize};
+
+/// Strategy of placing mesh points.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PointsPlacement {
+    /// Take the steepest remaining pixels that are separated from already placed points.
+    #[default]
+    Steepness,
+    /// Blue noise sampling of visible map area, where each point keeps distance from others
+    /// given by points separation at its location. Randomness is driven by settings seed.
+    PoissonDisc,
+    /// Start from map corners and keep inserting the pixel with the largest difference between
+    /// its value and the value interpolated over current triangulation.
+    /// Best suited for heightmaps approximation.
+    ErrorDriven {
+        /// Insertion stops when largest error is not greater than this value.
+        error_tolerance: Scalar,
+        /// Optional limit of mesh points.
+        max_points: Option<usize>,
+    },
+    /// Skip density points and triangulate simplified outline of visible area instead, which
+    /// produces low vertex count polygon mesh (like tight sprite meshes).
+    TightPolygon(ContourSimplification),
+}
+
+impl PointsPlacement {
+    /// Create error driven placement.
+    ///
+    /// # Arguments
+    /// * `error_tolerance` - Maximal accepted difference between map and mesh values.
+    pub fn error_driven(error_tolerance: Scalar) -> Self {
+        Self::ErrorDriven {
+            error_tolerance,
+            max_points: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        coord::Coord,
+        generator::{test_utils::*, DensityMeshGenerator},
+        mesh::{
+            points_separation::PointsSeparation, settings::GenerateDensityMeshSettings,
+            GenerateDensityMeshError,
+        },
+    };
+
+    fn poisson_disc_points(
+        separation: PointsSeparation,
+        seed: u64,
+    ) -> Result<Vec<Coord>, GenerateDensityMeshError> {
+        let settings = GenerateDensityMeshSettings {
+            points_separation: separation,
+            points_placement: PointsPlacement::PoissonDisc,
+            seed,
+            ..Default::default()
+        };
+        let mut generator = DensityMeshGenerator::new(vec![], wavy_map(48), settings);
+        generator.process_wait()?;
+        Ok(generator.into_mesh().unwrap().points)
+    }
+
+    #[test]
+    fn test_poisson_disc_seed() {
+        let first = poisson_disc_points(4.0.into(), 42).unwrap();
+        assert_eq!(first, poisson_disc_points(4.0.into(), 42).unwrap());
+        assert_ne!(first, poisson_disc_points(4.0.into(), 7).unwrap());
+        for (i, a) in first.iter().enumerate() {
+            for b in &first[i + 1..] {
+                assert!((*a - *b).magnitude() >= 4.0 - 1.0e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn test_poisson_disc_degenerate_separation() {
+        // sampling around points is skipped, so only seed pixels get placed.
+        let visible = wavy_map(48)
+            .values()
+            .iter()
+            .filter(|v| **v > GenerateDensityMeshSettings::default().visibility_threshold)
+            .count();
+        let points = poisson_disc_points(0.0.into(), 42).unwrap();
+        assert_eq!(points.len(), visible);
+        assert!(poisson_disc_points(Scalar::NAN.into(), 42).is_ok());
+        // single point cannot be triangulated, but processing finishes.
+        assert_eq!(
+            poisson_disc_points(Scalar::INFINITY.into(), 42),
+            Err(GenerateDensityMeshError::FailedTriangulation)
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use crate::Scalar;
 use serde::{Deserialize, Serialize};
-use std::{num::ParseFloatError, ops::Range, str::FromStr};
+use std::{fmt, num::ParseFloatError, ops::Range, str::FromStr};
 
 /// Point separation source.
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -21,6 +21,14 @@ impl PointsSeparation {
             Self::SteepnessMapping(_, v) => *v,
         }
     }
+
+    /// Returns separation of point placed where map has given steepness.
+    pub fn local(&self, steepness: Scalar) -> Scalar {
+        match self {
+            Self::Constant(v) => *v,
+            Self::SteepnessMapping(from, to) => to + (from - to) * steepness.clamp(0.0, 1.0),
+        }
+    }
 }
 
 impl From<Scalar> for PointsSeparation {
@@ -64,11 +72,11 @@ impl FromStr for PointsSeparation {
     }
 }
 
-impl ToString for PointsSeparation {
-    fn to_string(&self) -> String {
+impl fmt::Display for PointsSeparation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Self::Constant(v) => v.to_string(),
-            Self::SteepnessMapping(f, t) => format!("{}..{}", f, t),
+            Self::Constant(v) => write!(f, "{}", v),
+            Self::SteepnessMapping(from, to) => write!(f, "{}..{}", from, to),
         }
     }
 }
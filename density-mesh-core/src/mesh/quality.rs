@@ -0,0 +1,291 @@
+use crate::{
+    coord::Coord,
+    mesh::{DensityMesh, GenerateDensityMeshError},
+    Scalar,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Upper bounds of aspect ratio histogram buckets; the last bucket collects all bigger ratios.
+pub const ASPECT_RATIO_BUCKETS: [Scalar; 5] = [1.5, 2.0, 3.0, 5.0, 10.0];
+
+/// Geometric health report of density mesh triangles.
+///
+/// Degenerate and duplicate triangles are counted but excluded from angle, aspect ratio and area
+/// statistics.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QualityReport {
+    /// Number of triangles.
+    pub triangles: usize,
+    /// Number of triangles with repeated corners or zero area.
+    pub degenerate_triangles: usize,
+    /// Number of triangles using the same corners as another triangle.
+    pub duplicate_triangles: usize,
+    /// Minimal triangle angle in degrees.
+    pub min_angle: Scalar,
+    /// Maximal triangle angle in degrees.
+    pub max_angle: Scalar,
+    /// Mean of smallest angles of triangles in degrees.
+    pub mean_min_angle: Scalar,
+    /// Number of triangles in each aspect ratio bucket (see `ASPECT_RATIO_BUCKETS`).
+    /// Aspect ratio is circumradius divided by double inradius, which is 1 for equilateral
+    /// triangle.
+    pub aspect_ratio_histogram: Vec<usize>,
+    /// Minimal triangle area.
+    pub min_area: Scalar,
+    /// Maximal triangle area.
+    pub max_area: Scalar,
+    /// Mean triangle area.
+    pub mean_area: Scalar,
+    /// Median triangle area.
+    pub median_area: Scalar,
+    /// Sum of triangles area.
+    pub total_area: Scalar,
+}
+
+impl QualityReport {
+    /// Measure quality of density mesh triangles.
+    ///
+    /// # Arguments
+    /// * `mesh` - Density mesh.
+    ///
+    /// # Returns
+    /// Report or error if triangles point to missing points.
+    ///
+    /// # Examples
+    /// ```
+    /// use density_mesh_core::prelude::*;
+    ///
+    /// let mesh = DensityMesh {
+    ///     points: vec![
+    ///         Coord::new(0.0, 0.0),
+    ///         Coord::new(2.0, 0.0),
+    ///         Coord::new(2.0, 2.0),
+    ///         Coord::new(0.0, 2.0),
+    ///     ],
+    ///     triangles: vec![[0, 2, 1].into(), [0, 3, 2].into(), [2, 0, 3].into(), [0, 0, 1].into()],
+    ///     ..Default::default()
+    /// };
+    /// let report = QualityReport::new(&mesh).unwrap();
+    /// assert_eq!(report.triangles, 4);
+    /// assert_eq!(report.degenerate_triangles, 1);
+    /// assert_eq!(report.duplicate_triangles, 1);
+    /// assert!((report.min_angle - 45.0).abs() < 1.0e-3);
+    /// assert!((report.max_angle - 90.0).abs() < 1.0e-3);
+    /// assert_eq!(report.aspect_ratio_histogram, vec![2, 0, 0, 0, 0, 0]);
+    /// assert_eq!(report.total_area, 4.0);
+    /// ```
+    pub fn new(mesh: &DensityMesh) -> Result<Self, GenerateDensityMeshError> {
+        let count = mesh.points.len();
+        if mesh
+            .triangles
+            .iter()
+            .any(|t| t.a >= count || t.b >= count || t.c >= count)
+        {
+            return Err(GenerateDensityMeshError::InvalidTriangles);
+        }
+        let mut result = Self {
+            triangles: mesh.triangles.len(),
+            min_angle: if mesh.triangles.is_empty() {
+                0.0
+            } else {
+                180.0
+            },
+            aspect_ratio_histogram: vec![0; ASPECT_RATIO_BUCKETS.len() + 1],
+            ..Default::default()
+        };
+        let mut corners = HashSet::with_capacity(mesh.triangles.len());
+        let mut areas = Vec::with_capacity(mesh.triangles.len());
+        let mut min_angles_sum = 0.0;
+        for triangle in &mesh.triangles {
+            let mut key = [triangle.a, triangle.b, triangle.c];
+            key.sort_unstable();
+            let duplicate = !corners.insert(key);
+            if duplicate {
+                result.duplicate_triangles += 1;
+            }
+            let (a, b, c) = (
+                mesh.points[triangle.a],
+                mesh.points[triangle.b],
+                mesh.points[triangle.c],
+            );
+            let lengths = [
+                (c - b).magnitude(),
+                (a - c).magnitude(),
+                (b - a).magnitude(),
+            ];
+            let longest = lengths[0].max(lengths[1]).max(lengths[2]);
+            let area = cross(b - a, c - a).abs() * 0.5;
+            if key[0] == key[1] || key[1] == key[2] || area <= Scalar::EPSILON * longest * longest {
+                result.degenerate_triangles += 1;
+                continue;
+            }
+            if duplicate {
+                continue;
+            }
+            result.total_area += area;
+            areas.push(area);
+            let angles = [
+                angle(b - a, c - a),
+                angle(a - b, c - b),
+                angle(a - c, b - c),
+            ];
+            let min_angle = angles[0].min(angles[1]).min(angles[2]);
+            let max_angle = angles[0].max(angles[1]).max(angles[2]);
+            result.min_angle = result.min_angle.min(min_angle);
+            result.max_angle = result.max_angle.max(max_angle);
+            min_angles_sum += min_angle;
+            let perimeter = lengths[0] + lengths[1] + lengths[2];
+            let inradius = 2.0 * area / perimeter;
+            let circumradius = lengths[0] * lengths[1] * lengths[2] / (4.0 * area);
+            let ratio = circumradius / (2.0 * inradius);
+            let bucket = ASPECT_RATIO_BUCKETS
+                .iter()
+                .position(|bound| ratio <= *bound + Scalar::EPSILON * 16.0)
+                .unwrap_or(ASPECT_RATIO_BUCKETS.len());
+            result.aspect_ratio_histogram[bucket] += 1;
+        }
+        if !areas.is_empty() {
+            areas.sort_by(|a, b| a.total_cmp(b));
+            let half = areas.len() / 2;
+            result.min_area = areas[0];
+            result.max_area = areas[areas.len() - 1];
+            result.mean_area = result.total_area / areas.len() as Scalar;
+            result.median_area = if areas.len() % 2 == 0 {
+                (areas[half - 1] + areas[half]) * 0.5
+            } else {
+                areas[half]
+            };
+            result.mean_min_angle = min_angles_sum / areas.len() as Scalar;
+        } else {
+            result.min_angle = 0.0;
+        }
+        Ok(result)
+    }
+}
+
+fn cross(a: Coord, b: Coord) -> Scalar {
+    a.x * b.y - a.y * b.x
+}
+
+/// Returns angle between vectors in degrees.
+fn angle(a: Coord, b: Coord) -> Scalar {
+    cross(a, b).abs().atan2(a.dot(b)).to_degrees()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::triangle::Triangle;
+
+    /// Mesh made of separate triangles given by their corners.
+    fn mesh_of(corners: &[[Coord; 3]]) -> DensityMesh {
+        DensityMesh {
+            points: corners.iter().flatten().copied().collect(),
+            triangles: (0..corners.len())
+                .map(|i| Triangle::from([i * 3, i * 3 + 1, i * 3 + 2]))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Right triangle with legs of length `area` and 2.
+    fn with_area(area: Scalar) -> [Coord; 3] {
+        [
+            Coord::new(0.0, 0.0),
+            Coord::new(0.0, 2.0),
+            Coord::new(area, 0.0),
+        ]
+    }
+
+    /// Isosceles triangle with base 2 and given aspect ratio (at least 1).
+    fn with_aspect_ratio(ratio: f64) -> [Coord; 3] {
+        // aspect ratio of such triangle with arms of length `s` is `s^2 / (4 * (s - 1))`.
+        let arm = 2.0 * ratio + 2.0 * (ratio * ratio - ratio).sqrt();
+        let height = (arm * arm - 1.0).sqrt() as Scalar;
+        [
+            Coord::new(-1.0, 0.0),
+            Coord::new(0.0, height),
+            Coord::new(1.0, 0.0),
+        ]
+    }
+
+    #[test]
+    fn test_empty() {
+        let expected = QualityReport {
+            aspect_ratio_histogram: vec![0; ASPECT_RATIO_BUCKETS.len() + 1],
+            ..Default::default()
+        };
+        assert_eq!(
+            QualityReport::new(&DensityMesh::default()).unwrap(),
+            expected
+        );
+        // only degenerate triangles leave statistics empty too.
+        let report = QualityReport::new(&mesh_of(&[
+            [
+                Coord::new(0.0, 0.0),
+                Coord::new(1.0, 1.0),
+                Coord::new(2.0, 2.0),
+            ],
+            [
+                Coord::new(3.0, 0.0),
+                Coord::new(3.0, 0.0),
+                Coord::new(4.0, 1.0),
+            ],
+        ]))
+        .unwrap();
+        assert_eq!(
+            report,
+            QualityReport {
+                triangles: 2,
+                degenerate_triangles: 2,
+                ..expected
+            }
+        );
+    }
+
+    #[test]
+    fn test_median_area() {
+        let report =
+            QualityReport::new(&mesh_of(&[with_area(3.0), with_area(1.0), with_area(2.0)]))
+                .unwrap();
+        assert_eq!(report.median_area, 2.0);
+        assert_eq!(report.min_area, 1.0);
+        assert_eq!(report.max_area, 3.0);
+        assert_eq!(report.mean_area, 2.0);
+        assert_eq!(report.total_area, 6.0);
+
+        let report = QualityReport::new(&mesh_of(&[
+            with_area(4.0),
+            with_area(1.0),
+            with_area(3.0),
+            with_area(2.0),
+        ]))
+        .unwrap();
+        assert_eq!(report.median_area, 2.5);
+        assert_eq!(report.mean_area, 2.5);
+
+        let report = QualityReport::new(&mesh_of(&[with_area(5.0)])).unwrap();
+        assert_eq!(report.median_area, 5.0);
+        assert_eq!(report.min_area, report.max_area);
+    }
+
+    #[test]
+    fn test_aspect_ratio_buckets() {
+        let bucket = |ratio: f64| {
+            let report = QualityReport::new(&mesh_of(&[with_aspect_ratio(ratio)])).unwrap();
+            report
+                .aspect_ratio_histogram
+                .iter()
+                .position(|count| *count == 1)
+                .unwrap()
+        };
+        assert_eq!(bucket(1.0), 0);
+        for (index, bound) in ASPECT_RATIO_BUCKETS.iter().enumerate() {
+            let bound = *bound as f64;
+            assert_eq!(bucket(bound * 0.999), index, "{}", bound);
+            assert_eq!(bucket(bound * 1.001), index + 1, "{}", bound);
+        }
+        assert_eq!(bucket(1000.0), ASPECT_RATIO_BUCKETS.len());
+    }
+}
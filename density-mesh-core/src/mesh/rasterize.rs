@@ -0,0 +1,304 @@
+use crate::{
+    coord::Coord,
+    map::DensityMap,
+    mesh::{DensityMesh, GenerateDensityMeshError},
+    Scalar,
+};
+use serde::{Deserialize, Serialize};
+
+/// Settings of mesh rasterization into density map.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RasterizeSettings {
+    /// Number of samples per pixel along each axis. 1 samples only the pixel anchor, bigger
+    /// values produce anti-aliased coverage of triangle edges.
+    #[serde(default = "RasterizeSettings::default_samples")]
+    pub samples: usize,
+}
+
+impl Default for RasterizeSettings {
+    fn default() -> Self {
+        Self {
+            samples: Self::default_samples(),
+        }
+    }
+}
+
+impl RasterizeSettings {
+    fn default_samples() -> usize {
+        1
+    }
+}
+
+/// Render mesh into density map, where pixel value is either triangles coverage or height
+/// interpolated between triangle corners.
+///
+/// Pixel at `(col, row)` is sampled around point `(col * scale, row * scale)` of the mesh.
+///
+/// # Arguments
+/// * `mesh` - Rendered density mesh.
+/// * `width` - Map columns.
+/// * `height` - Map rows.
+/// * `scale` - Map scale.
+/// * `heights` - Height of each mesh point (in range from 0 to 1), or `None` for flat coverage.
+/// * `settings` - Rasterization settings.
+///
+/// # Returns
+/// Density map or error if triangles or heights do not match mesh points.
+///
+/// # Examples
+/// ```
+/// use density_mesh_core::prelude::*;
+///
+/// let mesh = DensityMesh {
+///     points: vec![Coord::new(0.0, 0.0), Coord::new(2.0, 0.0), Coord::new(0.0, 2.0)],
+///     triangles: vec![[0, 2, 1].into()],
+///     ..Default::default()
+/// };
+/// let map = rasterize(&mesh, 3, 3, 1, None, &RasterizeSettings::default()).unwrap();
+/// assert_eq!(map.values(), &[1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 1.0, 0.0, 0.0]);
+///
+/// let heights = [0.0, 1.0, 0.0];
+/// let map = rasterize(&mesh, 3, 3, 1, Some(&heights), &RasterizeSettings::default()).unwrap();
+/// assert_eq!(map.values(), &[0.0, 0.5, 1.0, 0.0, 0.5, 0.0, 0.0, 0.0, 0.0]);
+///
+/// let settings = RasterizeSettings { samples: 2 };
+/// let map = rasterize(&mesh, 3, 3, 1, None, &settings).unwrap();
+/// assert_eq!(map.values()[4], 0.75);
+/// ```
+pub fn rasterize(
+    mesh: &DensityMesh,
+    width: usize,
+    height: usize,
+    scale: usize,
+    heights: Option<&[Scalar]>,
+    settings: &RasterizeSettings,
+) -> Result<DensityMap, GenerateDensityMeshError> {
+    let count = mesh.points.len();
+    if mesh
+        .triangles
+        .iter()
+        .any(|t| t.a >= count || t.b >= count || t.c >= count)
+    {
+        return Err(GenerateDensityMeshError::InvalidTriangles);
+    }
+    if let Some(heights) = heights {
+        if heights.len() != count {
+            return Err(GenerateDensityMeshError::WrongAttributeLength(
+                heights.len(),
+                count,
+            ));
+        }
+    }
+    let samples = settings.samples.max(1);
+    let scale = scale.max(1);
+    let step = scale as Scalar / samples as Scalar;
+    // samples are spread evenly over pixel area centered at pixel anchor.
+    let offset = (step - scale as Scalar) * 0.5;
+    let columns = width * samples;
+    let rows = height * samples;
+    let mut buffer = vec![0.0; columns * rows];
+    for triangle in &mesh.triangles {
+        let (a, b, c) = (
+            mesh.points[triangle.a],
+            mesh.points[triangle.b],
+            mesh.points[triangle.c],
+        );
+        let area = cross(b - a, c - a);
+        if area == 0.0 {
+            continue;
+        }
+        let (ha, hb, hc) = match heights {
+            Some(heights) => (
+                heights[triangle.a],
+                heights[triangle.b],
+                heights[triangle.c],
+            ),
+            None => (1.0, 1.0, 1.0),
+        };
+        let min = Coord::new(a.x.min(b.x).min(c.x), a.y.min(b.y).min(c.y));
+        let max = Coord::new(a.x.max(b.x).max(c.x), a.y.max(b.y).max(c.y));
+        let fc = ((min.x - offset) / step).ceil().max(0.0) as usize;
+        let fr = ((min.y - offset) / step).ceil().max(0.0) as usize;
+        let tc = (((max.x - offset) / step).floor() + 1.0).clamp(0.0, columns as Scalar) as usize;
+        let tr = (((max.y - offset) / step).floor() + 1.0).clamp(0.0, rows as Scalar) as usize;
+        for row in fr..tr {
+            for col in fc..tc {
+                let p = Coord::new(col as Scalar * step + offset, row as Scalar * step + offset);
+                let wa = cross(b - p, c - p) / area;
+                let wb = cross(c - p, a - p) / area;
+                let wc = cross(a - p, b - p) / area;
+                if wa >= 0.0 && wb >= 0.0 && wc >= 0.0 {
+                    buffer[row * columns + col] = ha * wa + hb * wb + hc * wc;
+                }
+            }
+        }
+    }
+    let weight = 1.0 / (samples * samples) as Scalar;
+    let data = (0..width * height)
+        .map(|index| {
+            let (col, row) = (index % width, index / width);
+            (0..samples)
+                .flat_map(|y| (0..samples).map(move |x| (x, y)))
+                .map(|(x, y)| buffer[(row * samples + y) * columns + col * samples + x])
+                .sum::<Scalar>()
+                * weight
+        })
+        .collect::<Vec<_>>();
+    DensityMap::new_scalar(width, height, scale, data).map_err(GenerateDensityMeshError::DensityMap)
+}
+
+fn cross(a: Coord, b: Coord) -> Scalar {
+    a.x * b.y - a.y * b.x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::triangle::Triangle;
+
+    /// Rasterize by checking every sample against every triangle.
+    fn brute_rasterize(
+        mesh: &DensityMesh,
+        width: usize,
+        height: usize,
+        scale: usize,
+        heights: Option<&[Scalar]>,
+        samples: usize,
+    ) -> Vec<Scalar> {
+        let step = scale as Scalar / samples as Scalar;
+        let sample = |p: Coord| {
+            let mut result = 0.0;
+            for t in &mesh.triangles {
+                let (a, b, c) = (mesh.points[t.a], mesh.points[t.b], mesh.points[t.c]);
+                let area = cross(b - a, c - a);
+                let w = [
+                    cross(b - p, c - p) / area,
+                    cross(c - p, a - p) / area,
+                    cross(a - p, b - p) / area,
+                ];
+                if w.iter().all(|w| *w >= 0.0) {
+                    result = match heights {
+                        Some(h) => h[t.a] * w[0] + h[t.b] * w[1] + h[t.c] * w[2],
+                        None => 1.0,
+                    };
+                }
+            }
+            result
+        };
+        (0..width * height)
+            .map(|index| {
+                let anchor = Coord::new((index % width) as Scalar, (index / width) as Scalar)
+                    * scale as Scalar;
+                let start = anchor - Coord::new(scale as Scalar, scale as Scalar) * 0.5
+                    + Coord::new(step, step) * 0.5;
+                (0..samples * samples)
+                    .map(|s| {
+                        let (x, y) = ((s % samples) as Scalar, (s / samples) as Scalar);
+                        sample(start + Coord::new(x, y) * step)
+                    })
+                    .sum::<Scalar>()
+                    / (samples * samples) as Scalar
+            })
+            .collect()
+    }
+
+    fn assert_brute(mesh: &DensityMesh, size: usize, scale: usize, heights: Option<&[Scalar]>) {
+        for samples in 1..=3 {
+            let settings = RasterizeSettings { samples };
+            let map = rasterize(mesh, size, size, scale, heights, &settings).unwrap();
+            assert_eq!(map.scale(), scale);
+            let brute = brute_rasterize(mesh, size, size, scale, heights, samples);
+            for (value, expected) in map.values().iter().zip(brute.iter()) {
+                assert!((value - expected).abs() < 1.0e-5, "{:?}", map.values());
+            }
+        }
+    }
+
+    #[test]
+    fn test_outside_of_map() {
+        let mesh = DensityMesh {
+            points: vec![
+                Coord::new(-5.0, -5.0),
+                Coord::new(20.0, -5.0),
+                Coord::new(-5.0, 20.0),
+                Coord::new(40.0, 40.0),
+                Coord::new(50.0, 40.0),
+                Coord::new(40.0, 50.0),
+            ],
+            triangles: vec![[0, 2, 1].into(), [3, 5, 4].into()],
+            ..Default::default()
+        };
+        let map = rasterize(&mesh, 6, 6, 1, None, &Default::default()).unwrap();
+        assert!(map.values().iter().all(|v| (v - 1.0).abs() < 1.0e-5));
+        let heights = [0.0, 0.5, 1.0, 1.0, 1.0, 1.0];
+        assert_brute(&mesh, 6, 1, Some(&heights));
+
+        // mesh entirely past the map edges.
+        let mesh = DensityMesh {
+            points: mesh.points[3..].to_vec(),
+            triangles: vec![[0, 2, 1].into()],
+            ..Default::default()
+        };
+        let map = rasterize(&mesh, 6, 6, 1, None, &RasterizeSettings { samples: 4 }).unwrap();
+        assert!(map.values().iter().all(|v| *v == 0.0));
+    }
+
+    #[test]
+    fn test_reversed_winding() {
+        let points = vec![
+            Coord::new(0.5, 0.2),
+            Coord::new(7.3, 1.1),
+            Coord::new(2.4, 6.8),
+            Coord::new(7.9, 7.6),
+        ];
+        let mesh = DensityMesh {
+            points: points.clone(),
+            triangles: vec![[0, 2, 1].into(), [1, 2, 3].into()],
+            ..Default::default()
+        };
+        let reversed = DensityMesh {
+            points,
+            triangles: mesh
+                .triangles
+                .iter()
+                .map(|t| Triangle::from([t.a, t.c, t.b]))
+                .collect(),
+            ..Default::default()
+        };
+        let heights = [0.0, 0.3, 0.6, 1.0];
+        for samples in 1..=3 {
+            let settings = RasterizeSettings { samples };
+            let map = rasterize(&mesh, 8, 8, 1, Some(&heights), &settings).unwrap();
+            let other = rasterize(&reversed, 8, 8, 1, Some(&heights), &settings).unwrap();
+            for (a, b) in map.values().iter().zip(other.values().iter()) {
+                assert!((a - b).abs() < 1.0e-5);
+            }
+        }
+        assert_brute(&reversed, 8, 1, None);
+        assert_brute(&reversed, 8, 1, Some(&heights));
+    }
+
+    #[test]
+    fn test_scale() {
+        let mesh = DensityMesh {
+            points: vec![
+                Coord::new(-0.05, -0.05),
+                Coord::new(6.1, -0.05),
+                Coord::new(-0.05, 6.1),
+            ],
+            triangles: vec![[0, 2, 1].into()],
+            ..Default::default()
+        };
+        // pixel anchors are placed every 2 units of mesh.
+        let map = rasterize(&mesh, 4, 4, 2, None, &Default::default()).unwrap();
+        assert_eq!(map.unscaled_width(), 4);
+        for (index, value) in map.values().iter().enumerate() {
+            let (col, row) = (index % 4, index / 4);
+            let expected = if col + row <= 3 { 1.0 } else { 0.0 };
+            assert!((value - expected).abs() < 1.0e-5);
+        }
+        let heights = [1.0, 0.0, 0.5];
+        assert_brute(&mesh, 4, 2, Some(&heights));
+        assert_brute(&mesh, 5, 3, None);
+    }
+}
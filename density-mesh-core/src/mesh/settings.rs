@@ -1,4 +1,10 @@
-use crate::{mesh::points_separation::PointsSeparation, Scalar};
+use crate::{
+    mesh::{
+        attributes::MeshAttributes, extrude::ExtrudeSettings, points_placement::PointsPlacement,
+        points_separation::PointsSeparation, triangle_visibility::TriangleVisibility,
+    },
+    Scalar,
+};
 use serde::{Deserialize, Serialize};
 
 /// Settings of density mesh generation.
@@ -16,12 +22,49 @@ pub struct GenerateDensityMeshSettings {
     /// Limit of iterations when cannot find next available point.
     #[serde(default = "GenerateDensityMeshSettings::default_max_iterations")]
     pub max_iterations: usize,
+    /// Limit of rejected point candidates checked in single processing step.
+    /// Poisson-disc placement uses it as number of attempts around each active point.
+    /// Refinement checks up to this many triangles that need no Steiner point.
+    #[serde(default = "GenerateDensityMeshSettings::default_max_step_rejections")]
+    pub max_step_rejections: usize,
     /// Optional extrude size.
     #[serde(default)]
     pub extrude_size: Option<Scalar>,
-    /// Keep invisible triangles.
+    /// Shape of extruded outline, used when extrude size is set.
+    #[serde(default)]
+    pub extrude: ExtrudeSettings,
+    /// Per point attribute channels filled in produced mesh.
+    #[serde(default)]
+    pub attributes: MeshAttributes,
+    /// Keep invisible triangles. Triangles outside of constrained outline are removed anyway.
     #[serde(default)]
     pub keep_invisible_triangles: bool,
+    /// Criterion of triangle visibility, used when invisible triangles are removed.
+    #[serde(default)]
+    pub triangle_visibility: TriangleVisibility,
+    /// Margin around changed map region within which mesh gets regenerated.
+    #[serde(default)]
+    pub update_region_margin: Scalar,
+    /// Strategy of placing mesh points.
+    #[serde(default)]
+    pub points_placement: PointsPlacement,
+    /// Seed of random numbers used by points placement.
+    #[serde(default)]
+    pub seed: u64,
+    /// Constrain triangulation to the outline of visible area and keep only triangles inside.
+    #[serde(default)]
+    pub constrain_contour: bool,
+    /// Optional minimal angle (in degrees) of triangles, refined by inserting Steiner points.
+    /// Angles above 30 degrees might not be reachable within points limit, triangles smaller than
+    /// map pixel are not refined.
+    #[serde(default)]
+    pub min_angle: Option<Scalar>,
+    /// Optional maximal area of triangles, refined by inserting Steiner points.
+    #[serde(default)]
+    pub max_triangle_area: Option<Scalar>,
+    /// Optional limit of Steiner points inserted by refinement.
+    #[serde(default)]
+    pub max_steiner_points: Option<usize>,
 }
 
 impl Default for GenerateDensityMeshSettings {
@@ -31,8 +74,19 @@ impl Default for GenerateDensityMeshSettings {
             visibility_threshold: Self::default_visibility_threshold(),
             steepness_threshold: Self::default_steepness_threshold(),
             max_iterations: Self::default_max_iterations(),
+            max_step_rejections: Self::default_max_step_rejections(),
             extrude_size: None,
+            extrude: ExtrudeSettings::default(),
+            attributes: MeshAttributes::default(),
             keep_invisible_triangles: false,
+            triangle_visibility: TriangleVisibility::default(),
+            update_region_margin: 0.0,
+            points_placement: PointsPlacement::default(),
+            seed: 0,
+            constrain_contour: false,
+            min_angle: None,
+            max_triangle_area: None,
+            max_steiner_points: None,
         }
     }
 }
@@ -53,4 +107,8 @@ impl GenerateDensityMeshSettings {
     fn default_max_iterations() -> usize {
         32
     }
+
+    fn default_max_step_rejections() -> usize {
+        32
+    }
 }
@@ -0,0 +1,489 @@
+use crate::{coord::Coord, mesh::GenerateDensityMeshError, triangle::Triangle, Scalar};
+use std::collections::HashSet;
+
+/// Tolerance of barycentric weights, so points on shared edges are not missed.
+const WEIGHT_EPSILON: Scalar = 1.0e-5;
+
+/// Uniform grid index over mesh triangles and points, answering point location, nearest point
+/// and segment intersection queries.
+///
+/// # Examples
+/// ```
+/// use density_mesh_core::prelude::*;
+///
+/// let mesh = DensityMesh {
+///     points: vec![
+///         Coord::new(0.0, 0.0),
+///         Coord::new(4.0, 0.0),
+///         Coord::new(4.0, 4.0),
+///         Coord::new(0.0, 4.0),
+///     ],
+///     triangles: vec![[0, 2, 1].into(), [0, 3, 2].into()],
+///     ..Default::default()
+/// };
+/// let index = mesh.spatial_index().unwrap();
+/// let (triangle, weights) = index.locate(Coord::new(3.0, 1.0)).unwrap();
+/// assert_eq!(triangle, 0);
+/// assert_eq!(weights, [0.25, 0.25, 0.5]);
+/// assert_eq!(index.interpolate(Coord::new(1.0, 1.0), &[1.0, 1.0, 0.0, 1.0]), Some(0.75));
+/// assert_eq!(index.nearest_point(Coord::new(5.0, 5.0)), Some(2));
+/// let (_, factor) = index
+///     .intersect_segment(Coord::new(-4.0, 2.0), Coord::new(4.0, 2.0))
+///     .unwrap();
+/// assert_eq!(factor, 0.5);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SpatialIndex<'a> {
+    points: &'a [Coord],
+    triangles: &'a [Triangle],
+    min: Coord,
+    cell_size: Scalar,
+    cols: usize,
+    rows: usize,
+    /// Triangles which bounding box overlaps each cell.
+    cells: Vec<Vec<usize>>,
+    /// Points placed in each cell.
+    point_cells: Vec<Vec<usize>>,
+}
+
+impl<'a> SpatialIndex<'a> {
+    /// Create spatial index.
+    ///
+    /// # Arguments
+    /// * `points` - Mesh points.
+    /// * `triangles` - Mesh triangles.
+    ///
+    /// # Returns
+    /// Spatial index or error if triangles point to missing points.
+    pub fn new(
+        points: &'a [Coord],
+        triangles: &'a [Triangle],
+    ) -> Result<Self, GenerateDensityMeshError> {
+        if triangles
+            .iter()
+            .any(|t| t.a >= points.len() || t.b >= points.len() || t.c >= points.len())
+        {
+            return Err(GenerateDensityMeshError::InvalidTriangles);
+        }
+        let (min, max) = points.iter().fold(
+            (
+                Coord::new(Scalar::INFINITY, Scalar::INFINITY),
+                Coord::new(Scalar::NEG_INFINITY, Scalar::NEG_INFINITY),
+            ),
+            |(min, max), p| {
+                (
+                    Coord::new(min.x.min(p.x), min.y.min(p.y)),
+                    Coord::new(max.x.max(p.x), max.y.max(p.y)),
+                )
+            },
+        );
+        let (min, size) = if points.is_empty() {
+            (Coord::default(), Coord::default())
+        } else {
+            (min, max - min)
+        };
+        // about one triangle per cell.
+        let count = triangles.len().max(points.len()).max(1) as Scalar;
+        let cell_size = (size.x.max(size.y) / count.sqrt()).max(1.0e-3);
+        let cols = ((size.x / cell_size) as usize + 1).min(1024);
+        let rows = ((size.y / cell_size) as usize + 1).min(1024);
+        let cell_size = cell_size
+            .max(size.x / cols as Scalar)
+            .max(size.y / rows as Scalar);
+        let mut result = Self {
+            points,
+            triangles,
+            min,
+            cell_size,
+            cols,
+            rows,
+            cells: vec![vec![]; cols * rows],
+            point_cells: vec![vec![]; cols * rows],
+        };
+        for (index, t) in triangles.iter().enumerate() {
+            let (a, b, c) = (points[t.a], points[t.b], points[t.c]);
+            let (fc, fr) = result.cell(Coord::new(a.x.min(b.x).min(c.x), a.y.min(b.y).min(c.y)));
+            let (tc, tr) = result.cell(Coord::new(a.x.max(b.x).max(c.x), a.y.max(b.y).max(c.y)));
+            for row in fr..=tr {
+                for col in fc..=tc {
+                    result.cells[row * cols + col].push(index);
+                }
+            }
+        }
+        for (index, p) in points.iter().enumerate() {
+            let (col, row) = result.cell(*p);
+            result.point_cells[row * cols + col].push(index);
+        }
+        Ok(result)
+    }
+
+    /// Find triangle containing given point.
+    ///
+    /// # Returns
+    /// `(triangle index, [barycentric weight of corner a, b and c])` or `None` if point is not
+    /// covered by mesh.
+    pub fn locate(&self, point: Coord) -> Option<(usize, [Scalar; 3])> {
+        let (col, row) = self.cell_of(point)?;
+        self.cells[row * self.cols + col]
+            .iter()
+            .filter_map(|index| Some((*index, self.weights(*index, point)?)))
+            .find(|(_, weights)| weights.iter().all(|w| *w >= -WEIGHT_EPSILON))
+    }
+
+    /// Interpolate per point values at given point.
+    ///
+    /// # Arguments
+    /// * `point` - Sampled point.
+    /// * `values` - Value of each mesh point.
+    ///
+    /// # Returns
+    /// Interpolated value or `None` if point is not covered by mesh.
+    pub fn interpolate(&self, point: Coord, values: &[Scalar]) -> Option<Scalar> {
+        let (index, [wa, wb, wc]) = self.locate(point)?;
+        let t = &self.triangles[index];
+        Some(values.get(t.a)? * wa + values.get(t.b)? * wb + values.get(t.c)? * wc)
+    }
+
+    /// Find mesh point closest to given point.
+    ///
+    /// # Returns
+    /// Point index or `None` if mesh has no points.
+    pub fn nearest_point(&self, point: Coord) -> Option<usize> {
+        if self.points.is_empty() {
+            return None;
+        }
+        let (col, row) = self.cell(point);
+        let (col, row) = (col as isize, row as isize);
+        let mut result: Option<(usize, Scalar)> = None;
+        for radius in 0..=(self.cols.max(self.rows) as isize) {
+            // points in further rings are farther than found one.
+            if let Some((_, sqr_distance)) = result {
+                let reach = (radius - 1).max(0) as Scalar * self.cell_size;
+                if reach * reach > sqr_distance {
+                    break;
+                }
+            }
+            for r in (row - radius)..=(row + radius) {
+                for c in (col - radius)..=(col + radius) {
+                    let on_ring = (r - row).abs() == radius || (c - col).abs() == radius;
+                    if !on_ring
+                        || r < 0
+                        || c < 0
+                        || r >= self.rows as isize
+                        || c >= self.cols as isize
+                    {
+                        continue;
+                    }
+                    for index in &self.point_cells[r as usize * self.cols + c as usize] {
+                        let sqr_distance = (self.points[*index] - point).sqr_magnitude();
+                        if result.map(|(_, d)| sqr_distance < d).unwrap_or(true) {
+                            result = Some((*index, sqr_distance));
+                        }
+                    }
+                }
+            }
+        }
+        result.map(|(index, _)| index)
+    }
+
+    /// Find the first place where segment enters mesh.
+    ///
+    /// # Arguments
+    /// * `from` - Segment start.
+    /// * `to` - Segment end.
+    ///
+    /// # Returns
+    /// `(triangle index, factor)` where point of intersection is `from + (to - from) * factor`,
+    /// or `None` if segment does not touch mesh.
+    pub fn intersect_segment(&self, from: Coord, to: Coord) -> Option<(usize, Scalar)> {
+        let direction = to - from;
+        let mut result: Option<(usize, Scalar)> = None;
+        let mut checked = HashSet::new();
+        // walk through cells crossed by the segment, in order.
+        let steps = ((direction.x.abs().max(direction.y.abs()) / self.cell_size) * 2.0).ceil();
+        let steps = (steps as usize).clamp(1, (self.cols + self.rows) * 2);
+        let mut last = None;
+        for step in 0..=steps {
+            let factor = step as Scalar / steps as Scalar;
+            if let Some((_, found)) = result {
+                // cells further away can not have closer intersection.
+                let reach = (factor - 1.0 / steps as Scalar) * direction.magnitude();
+                if reach > found * direction.magnitude() + self.cell_size * 2.0 {
+                    break;
+                }
+            }
+            let (col, row) = self.cell(from + direction * factor);
+            for r in row.saturating_sub(1)..(row + 2).min(self.rows) {
+                for c in col.saturating_sub(1)..(col + 2).min(self.cols) {
+                    if last == Some((c, r)) {
+                        continue;
+                    }
+                    for index in &self.cells[r * self.cols + c] {
+                        if !checked.insert(*index) {
+                            continue;
+                        }
+                        if let Some(found) = self.segment_entry(*index, from, to) {
+                            if result.map(|(_, f)| found < f).unwrap_or(true) {
+                                result = Some((*index, found));
+                            }
+                        }
+                    }
+                }
+            }
+            last = Some((col, row));
+        }
+        result
+    }
+
+    /// Find the first place where ray enters mesh.
+    ///
+    /// # Arguments
+    /// * `origin` - Ray origin.
+    /// * `direction` - Ray direction.
+    ///
+    /// # Returns
+    /// `(triangle index, distance)` or `None` if ray does not touch mesh.
+    pub fn intersect_ray(&self, origin: Coord, direction: Coord) -> Option<(usize, Scalar)> {
+        let length = direction.magnitude();
+        if length <= 0.0 {
+            return self.locate(origin).map(|(index, _)| (index, 0.0));
+        }
+        let direction = direction / length;
+        let extent = self.cell_size * (self.cols + self.rows) as Scalar;
+        let reach = (origin - self.min).magnitude() + extent;
+        self.intersect_segment(origin, origin + direction * reach)
+            .map(|(index, factor)| (index, factor * reach))
+    }
+
+    fn weights(&self, index: usize, point: Coord) -> Option<[Scalar; 3]> {
+        let t = &self.triangles[index];
+        let (a, b, c) = (self.points[t.a], self.points[t.b], self.points[t.c]);
+        let area = cross(b - a, c - a);
+        if area == 0.0 {
+            return None;
+        }
+        Some([
+            cross(b - point, c - point) / area,
+            cross(c - point, a - point) / area,
+            cross(a - point, b - point) / area,
+        ])
+    }
+
+    /// Returns factor of the first point of segment that lays in triangle.
+    fn segment_entry(&self, index: usize, from: Coord, to: Coord) -> Option<Scalar> {
+        if let Some(weights) = self.weights(index, from) {
+            if weights.iter().all(|w| *w >= -WEIGHT_EPSILON) {
+                return Some(0.0);
+            }
+        }
+        let t = &self.triangles[index];
+        let corners = [self.points[t.a], self.points[t.b], self.points[t.c]];
+        let direction = to - from;
+        (0..3)
+            .filter_map(|i| {
+                let (a, b) = (corners[i], corners[(i + 1) % 3]);
+                let edge = b - a;
+                let denominator = cross(direction, edge);
+                if denominator == 0.0 {
+                    return None;
+                }
+                let factor = cross(a - from, edge) / denominator;
+                let along = cross(a - from, direction) / denominator;
+                if (0.0..=1.0).contains(&factor) && (0.0..=1.0).contains(&along) {
+                    Some(factor)
+                } else {
+                    None
+                }
+            })
+            .fold(None, |result: Option<Scalar>, factor| {
+                Some(result.map(|r| r.min(factor)).unwrap_or(factor))
+            })
+    }
+
+    /// Returns cell containing point, clamped to the grid.
+    fn cell(&self, point: Coord) -> (usize, usize) {
+        let local = (point - self.min) / self.cell_size;
+        (
+            (local.x.max(0.0) as usize).min(self.cols - 1),
+            (local.y.max(0.0) as usize).min(self.rows - 1),
+        )
+    }
+
+    /// Returns cell containing point or `None` if point is outside of the grid.
+    fn cell_of(&self, point: Coord) -> Option<(usize, usize)> {
+        let local = (point - self.min) / self.cell_size;
+        let epsilon = WEIGHT_EPSILON;
+        if local.x < -epsilon
+            || local.y < -epsilon
+            || local.x > self.cols as Scalar + epsilon
+            || local.y > self.rows as Scalar + epsilon
+        {
+            return None;
+        }
+        Some(self.cell(point))
+    }
+}
+
+fn cross(a: Coord, b: Coord) -> Scalar {
+    a.x * b.y - a.y * b.x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generator::test_utils::cells_grid, mesh::DensityMesh};
+
+    /// Linear congruential generator of values in `[0; 1)`, stable between runs.
+    fn random(mut seed: u64) -> impl FnMut() -> Scalar {
+        move || {
+            seed = seed
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (seed >> 33) as Scalar / (1u64 << 31) as Scalar
+        }
+    }
+
+    /// 8x8 cells grid with 2x2 cells hole and randomly moved points.
+    fn random_mesh() -> DensityMesh {
+        let mut random = random(0x5eed);
+        let mut mesh = cells_grid(8, &[27, 28, 35, 36]);
+        for point in &mut mesh.points {
+            *point = *point + Coord::new(random() - 0.5, random() - 0.5) * 0.4;
+        }
+        mesh
+    }
+
+    fn contains(mesh: &DensityMesh, t: &Triangle, point: Coord) -> bool {
+        let (a, b, c) = (mesh.points[t.a], mesh.points[t.b], mesh.points[t.c]);
+        let area = cross(b - a, c - a);
+        [
+            cross(b - point, c - point) / area,
+            cross(c - point, a - point) / area,
+            cross(a - point, b - point) / area,
+        ]
+        .iter()
+        .all(|w| *w >= -1.0e-4)
+    }
+
+    /// Smallest factor of segment point inside of any triangle, checking all triangles.
+    fn brute_entry(mesh: &DensityMesh, from: Coord, to: Coord) -> Option<Scalar> {
+        let direction = to - from;
+        let mut result: Option<Scalar> = None;
+        for t in &mesh.triangles {
+            if contains(mesh, t, from) {
+                return Some(0.0);
+            }
+            for (a, b) in [(t.a, t.b), (t.b, t.c), (t.c, t.a)] {
+                let (a, edge) = (mesh.points[a], mesh.points[b] - mesh.points[a]);
+                let denominator = cross(direction, edge);
+                if denominator == 0.0 {
+                    continue;
+                }
+                let factor = cross(a - from, edge) / denominator;
+                let along = cross(a - from, direction) / denominator;
+                if (0.0..=1.0).contains(&factor) && (0.0..=1.0).contains(&along) {
+                    result = Some(result.map(|r| r.min(factor)).unwrap_or(factor));
+                }
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn test_locate() {
+        let mesh = random_mesh();
+        let index = mesh.spatial_index().unwrap();
+        let mut random = random(1);
+        // random points, also outside of mesh, points and middles of edges.
+        let queries = (0..2000)
+            .map(|_| Coord::new(random() * 12.0 - 2.0, random() * 12.0 - 2.0))
+            .chain(mesh.points.iter().copied())
+            .chain(mesh.triangles.iter().flat_map(|t| {
+                let [a, b, c] = [mesh.points[t.a], mesh.points[t.b], mesh.points[t.c]];
+                [(a + b) * 0.5, (b + c) * 0.5, (c + a) * 0.5]
+            }))
+            .collect::<Vec<_>>();
+        for point in queries {
+            let brute = mesh
+                .triangles
+                .iter()
+                .enumerate()
+                .filter(|(_, t)| contains(&mesh, t, point))
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>();
+            match index.locate(point) {
+                Some((triangle, weights)) => {
+                    assert!(brute.contains(&triangle), "{:?}", point);
+                    let t = &mesh.triangles[triangle];
+                    let located = mesh.points[t.a] * weights[0]
+                        + mesh.points[t.b] * weights[1]
+                        + mesh.points[t.c] * weights[2];
+                    assert!((located - point).magnitude() < 1.0e-4);
+                }
+                None => assert!(brute.is_empty(), "{:?}", point),
+            }
+        }
+        // inside of the hole.
+        assert_eq!(index.locate(Coord::new(4.0, 4.0)), None);
+    }
+
+    #[test]
+    fn test_nearest_point() {
+        let mesh = random_mesh();
+        let index = mesh.spatial_index().unwrap();
+        let mut random = random(2);
+        for _ in 0..2000 {
+            // far outside of mesh bounds as well.
+            let point = Coord::new(random() * 60.0 - 25.0, random() * 60.0 - 25.0);
+            let found = index.nearest_point(point).unwrap();
+            let brute = mesh
+                .points
+                .iter()
+                .map(|p| (*p - point).sqr_magnitude())
+                .fold(Scalar::INFINITY, Scalar::min);
+            assert_eq!((mesh.points[found] - point).sqr_magnitude(), brute);
+        }
+        let empty = DensityMesh::default();
+        assert_eq!(
+            empty
+                .spatial_index()
+                .unwrap()
+                .nearest_point(Coord::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_intersect_segment() {
+        let mesh = random_mesh();
+        let index = mesh.spatial_index().unwrap();
+        let mut random = random(3);
+        let mut misses = 0;
+        for _ in 0..2000 {
+            let from = Coord::new(random() * 16.0 - 4.0, random() * 16.0 - 4.0);
+            let to = from + Coord::new(random() - 0.5, random() - 0.5) * random() * 12.0;
+            let found = index.intersect_segment(from, to);
+            match brute_entry(&mesh, from, to) {
+                Some(brute) => {
+                    let (_, factor) = found.unwrap();
+                    assert!((factor - brute).abs() < 1.0e-4, "{:?} {:?}", from, to);
+                }
+                None => {
+                    assert_eq!(found, None, "{:?} {:?}", from, to);
+                    misses += 1;
+                }
+            }
+        }
+        assert!(misses > 0);
+        // along mesh side, inside of the hole and far away from the mesh.
+        for (from, to) in [
+            (Coord::new(-3.0, -1.0), Coord::new(11.0, -1.0)),
+            (Coord::new(3.5, 3.5), Coord::new(4.5, 4.5)),
+            (Coord::new(3.5, 4.5), Coord::new(4.5, 3.5)),
+            (Coord::new(-30.0, 50.0), Coord::new(50.0, 50.0)),
+        ] {
+            assert_eq!(brute_entry(&mesh, from, to), None);
+            assert_eq!(index.intersect_segment(from, to), None);
+        }
+    }
+}
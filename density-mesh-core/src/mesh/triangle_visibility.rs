@@ -0,0 +1,126 @@
+use crate::Scalar;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// Error thrown when triangle visibility cannot be parsed from string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseTriangleVisibilityError(pub String);
+
+/// Criterion that decides which triangles are kept when invisible triangles get removed.
+/// Pixels are visible when their value is above visibility threshold of mesh settings.
+///
+/// Triangles smaller than a pixel, which cover no pixel anchor, are always decided by the
+/// density map value sampled at their centroid.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TriangleVisibility {
+    /// Ratio of visible pixels covered by triangle is above given value.
+    CoverageRatio(Scalar),
+    /// At least one covered pixel is visible (conservative).
+    AnyPixel,
+    /// All covered pixels are visible.
+    AllPixels,
+    /// Density map value sampled at triangle centroid is visible.
+    Centroid,
+    /// Mean value of covered pixels is above given value.
+    MeanDensity(Scalar),
+}
+
+impl Default for TriangleVisibility {
+    fn default() -> Self {
+        Self::CoverageRatio(0.5)
+    }
+}
+
+impl TriangleVisibility {
+    /// Decide visibility of triangle.
+    ///
+    /// # Arguments
+    /// * `visible` - Number of visible pixels covered by triangle.
+    /// * `samples` - Number of pixels covered by triangle.
+    /// * `density` - Sum of values of pixels covered by triangle.
+    /// * `centroid` - Value sampled at triangle centroid.
+    /// * `visibility_threshold` - Pixels with value above it are visible.
+    ///
+    /// # Examples
+    /// ```
+    /// use density_mesh_core::prelude::*;
+    ///
+    /// let visibility = TriangleVisibility::default();
+    /// assert!(visibility.is_visible(3, 4, 3.0, 0.0, 0.01));
+    /// assert!(!visibility.is_visible(2, 4, 2.0, 1.0, 0.01));
+    /// assert!(TriangleVisibility::AnyPixel.is_visible(1, 4, 1.0, 0.0, 0.01));
+    /// assert!(!TriangleVisibility::AllPixels.is_visible(3, 4, 3.0, 1.0, 0.01));
+    /// assert!(TriangleVisibility::MeanDensity(0.25).is_visible(1, 2, 0.6, 0.0, 0.01));
+    /// // sub-pixel triangles are decided by centroid.
+    /// assert!(visibility.is_visible(0, 0, 0.0, 1.0, 0.01));
+    /// assert!(!TriangleVisibility::MeanDensity(0.5).is_visible(0, 0, 0.0, 0.4, 0.01));
+    /// ```
+    pub fn is_visible(
+        &self,
+        visible: usize,
+        samples: usize,
+        density: Scalar,
+        centroid: Scalar,
+        visibility_threshold: Scalar,
+    ) -> bool {
+        if samples == 0 {
+            return match self {
+                Self::MeanDensity(threshold) => centroid > *threshold,
+                _ => centroid > visibility_threshold,
+            };
+        }
+        match self {
+            Self::CoverageRatio(ratio) => visible as Scalar / samples as Scalar > *ratio,
+            Self::AnyPixel => visible > 0,
+            Self::AllPixels => visible == samples,
+            Self::Centroid => centroid > visibility_threshold,
+            Self::MeanDensity(threshold) => density / samples as Scalar > *threshold,
+        }
+    }
+
+    /// Tells if criterion needs sum of covered pixels values.
+    pub fn needs_density(&self) -> bool {
+        matches!(self, Self::MeanDensity(_))
+    }
+}
+
+impl FromStr for TriangleVisibility {
+    type Err = ParseTriangleVisibilityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "coverage" => Ok(Self::default()),
+            "any-pixel" => Ok(Self::AnyPixel),
+            "all-pixels" => Ok(Self::AllPixels),
+            "centroid" => Ok(Self::Centroid),
+            "mean-density" => Ok(Self::MeanDensity(0.5)),
+            _ => {
+                if let Some(ratio) = s.strip_prefix("coverage:") {
+                    ratio
+                        .parse::<Scalar>()
+                        .map(Self::CoverageRatio)
+                        .map_err(|_| ParseTriangleVisibilityError(s.to_owned()))
+                } else if let Some(threshold) = s.strip_prefix("mean-density:") {
+                    threshold
+                        .parse::<Scalar>()
+                        .map(Self::MeanDensity)
+                        .map_err(|_| ParseTriangleVisibilityError(s.to_owned()))
+                } else {
+                    Err(ParseTriangleVisibilityError(s.to_owned()))
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for TriangleVisibility {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::CoverageRatio(ratio) => write!(f, "coverage:{}", ratio),
+            Self::AnyPixel => write!(f, "any-pixel"),
+            Self::AllPixels => write!(f, "all-pixels"),
+            Self::Centroid => write!(f, "centroid"),
+            Self::MeanDensity(threshold) => write!(f, "mean-density:{}", threshold),
+        }
+    }
+}
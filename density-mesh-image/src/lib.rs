@@ -5,7 +5,7 @@ use density_mesh_core::{
     map::{DensityMap, DensityMapError},
     Scalar,
 };
-use image::{imageops::FilterType, DynamicImage, GenericImageView, GrayImage};
+use image::{imageops::FilterType, ColorType, DynamicImage, GenericImageView, GrayImage};
 
 /// Generate density map image.
 ///
@@ -39,6 +39,7 @@ pub fn generate_densitymap_image(
 }
 
 /// Generate density map from image.
+/// 16-bit images produce density map with 16-bit precision.
 ///
 /// # Arguments
 /// * `image` - Input image to process.
@@ -60,69 +61,124 @@ pub fn generate_densitymap_from_image(
     } else {
         image
     };
+    match image.color() {
+        ColorType::L16 | ColorType::La16 | ColorType::Rgb16 | ColorType::Rgba16 => {
+            return generate_densitymap_from_image16(image, scale, settings);
+        }
+        _ => {}
+    }
     match settings.density_source {
         ImageDensitySource::Luma => {
-            let img = image.to_luma();
-            DensityMap::new(img.width() as _, img.height() as _, scale, img.into_raw())
+            let img = image.to_luma8();
+            DensityMap::new_with_kernel(
+                img.width() as _,
+                img.height() as _,
+                scale,
+                img.into_raw(),
+                settings.steepness_kernel,
+            )
         }
         ImageDensitySource::LumaAlpha => {
             let w = image.width();
             let h = image.height();
-            let img = image.to_luma_alpha();
+            let img = image.to_luma_alpha8();
             let data = img
                 .into_raw()
                 .chunks(2)
                 .map(|c| ((c[0] as Scalar / 255.0) * (c[1] as Scalar / 255.0) * 255.0) as u8)
                 .collect::<Vec<_>>();
-            DensityMap::new(w as _, h as _, scale, data)
+            DensityMap::new_with_kernel(w as _, h as _, scale, data, settings.steepness_kernel)
         }
         ImageDensitySource::Red => {
             let w = image.width();
             let h = image.height();
             let data = image
-                .to_rgba()
+                .to_rgba8()
                 .into_raw()
                 .chunks(4)
                 .map(|c| c[0])
                 .collect::<Vec<_>>();
-            DensityMap::new(w as _, h as _, scale, data)
+            DensityMap::new_with_kernel(w as _, h as _, scale, data, settings.steepness_kernel)
         }
         ImageDensitySource::Green => {
             let w = image.width();
             let h = image.height();
             let data = image
-                .to_rgba()
+                .to_rgba8()
                 .into_raw()
                 .chunks(4)
                 .map(|c| c[1])
                 .collect::<Vec<_>>();
-            DensityMap::new(w as _, h as _, scale, data)
+            DensityMap::new_with_kernel(w as _, h as _, scale, data, settings.steepness_kernel)
         }
         ImageDensitySource::Blue => {
             let w = image.width();
             let h = image.height();
             let data = image
-                .to_rgba()
+                .to_rgba8()
                 .into_raw()
                 .chunks(4)
                 .map(|c| c[2])
                 .collect::<Vec<_>>();
-            DensityMap::new(w as _, h as _, scale, data)
+            DensityMap::new_with_kernel(w as _, h as _, scale, data, settings.steepness_kernel)
         }
         ImageDensitySource::Alpha => {
             let w = image.width();
             let h = image.height();
             let data = image
-                .to_rgba()
+                .to_rgba8()
                 .into_raw()
                 .chunks(4)
                 .map(|c| c[3])
                 .collect::<Vec<_>>();
-            DensityMap::new(w as _, h as _, scale, data)
+            DensityMap::new_with_kernel(w as _, h as _, scale, data, settings.steepness_kernel)
         }
     }
 }
 
+fn generate_densitymap_from_image16(
+    image: DynamicImage,
+    scale: usize,
+    settings: &GenerateDensityImageSettings,
+) -> Result<DensityMap, DensityMapError> {
+    let w = image.width();
+    let h = image.height();
+    let data = match settings.density_source {
+        ImageDensitySource::Luma => image.to_luma16().into_raw(),
+        ImageDensitySource::LumaAlpha => image
+            .to_luma_alpha16()
+            .into_raw()
+            .chunks(2)
+            .map(|c| ((c[0] as Scalar / 65535.0) * (c[1] as Scalar / 65535.0) * 65535.0) as u16)
+            .collect::<Vec<_>>(),
+        ImageDensitySource::Red => image
+            .to_rgba16()
+            .into_raw()
+            .chunks(4)
+            .map(|c| c[0])
+            .collect::<Vec<_>>(),
+        ImageDensitySource::Green => image
+            .to_rgba16()
+            .into_raw()
+            .chunks(4)
+            .map(|c| c[1])
+            .collect::<Vec<_>>(),
+        ImageDensitySource::Blue => image
+            .to_rgba16()
+            .into_raw()
+            .chunks(4)
+            .map(|c| c[2])
+            .collect::<Vec<_>>(),
+        ImageDensitySource::Alpha => image
+            .to_rgba16()
+            .into_raw()
+            .chunks(4)
+            .map(|c| c[3])
+            .collect::<Vec<_>>(),
+    };
+    DensityMap::new_u16_with_kernel(w as _, h as _, scale, data, settings.steepness_kernel)
+}
+
 /// Generate image from density map.
 ///
 /// # Arguments
@@ -158,3 +214,41 @@ pub mod prelude {
         settings::*,
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Luma, LumaA};
+
+    #[test]
+    fn test_image16_precision() {
+        let levels = [30000u16, 30001, 0, 65535];
+        let luma = ImageBuffer::<Luma<u16>, _>::from_raw(4, 1, levels.to_vec()).unwrap();
+        let luma_alpha = ImageBuffer::<LumaA<u16>, _>::from_raw(
+            4,
+            1,
+            levels.iter().flat_map(|v| vec![*v, 65535]).collect(),
+        )
+        .unwrap();
+        for (image, density_source) in [
+            (DynamicImage::ImageLuma16(luma), ImageDensitySource::Luma),
+            (
+                DynamicImage::ImageLumaA16(luma_alpha),
+                ImageDensitySource::LumaAlpha,
+            ),
+        ] {
+            let settings = GenerateDensityImageSettings {
+                density_source,
+                ..Default::default()
+            };
+            let map = generate_densitymap_from_image(image, &settings).unwrap();
+            let values = map.values();
+            // neighbour 16-bit levels fall into the same 8-bit level.
+            assert_eq!((values[0] * 255.0) as u8, (values[1] * 255.0) as u8);
+            assert!(values[0] < values[1]);
+            assert!((values[0] - 30000.0 / 65535.0).abs() < 1.0e-6);
+            assert_eq!(values[2], 0.0);
+            assert_eq!(values[3], 1.0);
+        }
+    }
+}
@@ -1,12 +1,14 @@
+use density_mesh_core::map::steepness_kernel::SteepnessKernel;
 use serde::{Deserialize, Serialize};
 
 /// Source image preprocessing mode (at the end you get grayscale image representing density map
 /// or typically a height map).
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
 pub enum ImageDensitySource {
     /// Luminosity.
     Luma,
     /// Luminosity * Alpha.
+    #[default]
     LumaAlpha,
     /// Red channel.
     Red,
@@ -18,12 +20,6 @@ pub enum ImageDensitySource {
     Alpha,
 }
 
-impl Default for ImageDensitySource {
-    fn default() -> Self {
-        Self::LumaAlpha
-    }
-}
-
 /// Settings of density image generation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GenerateDensityImageSettings {
@@ -33,6 +29,9 @@ pub struct GenerateDensityImageSettings {
     /// Scale of the image (image is rescaled to: original size / scale).
     #[serde(default = "GenerateDensityImageSettings::default_scale")]
     pub scale: usize,
+    /// Operator used to calculate steepness of density map.
+    #[serde(default)]
+    pub steepness_kernel: SteepnessKernel,
 }
 
 impl Default for GenerateDensityImageSettings {
@@ -40,6 +39,7 @@ impl Default for GenerateDensityImageSettings {
         Self {
             density_source: ImageDensitySource::default(),
             scale: GenerateDensityImageSettings::default_scale(),
+            steepness_kernel: SteepnessKernel::default(),
         }
     }
 }